@@ -0,0 +1,303 @@
+mod common;
+
+use clap::Parser;
+use common::{random_opening, Rng};
+use rust_chess::{
+    search::{
+        is_mate_score, search, SearchLimits, SearchOptions, Skill, TranspositionTable, MAX_ELO,
+        MAX_SKILL_LEVEL, MIN_ELO,
+    },
+    Color, GameState,
+};
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc,
+    },
+    thread,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+#[cfg(feature = "jemalloc")]
+#[global_allocator]
+static GLOBAL: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
+
+const MAX_GAME_PLIES: usize = 300;
+const MIN_RATING: f64 = -1000.0;
+const MAX_RATING: f64 = 5000.0;
+
+/// plays every skill level against opponents searching to a fixed depth and
+/// estimates the rating of each level from the ratings given to the opponents
+#[derive(Parser)]
+#[command(author, version, about, long_about = None)]
+struct Cli {
+    /// the skill levels to rate
+    #[arg(short, long, value_delimiter = ',', default_values_t = [0, 5, 10, 15, 20])]
+    levels: Vec<u32>,
+    /// depth:elo, the rating an opponent searching to that depth is assumed to have
+    #[arg(short, long, value_delimiter = ',', value_parser = parse_reference,
+          default_values = ["1:1200", "3:1700", "5:2200"])]
+    reference: Vec<Reference>,
+    /// per level and opponent, half of them with each color
+    #[arg(short, long, default_value_t = 40)]
+    games: usize,
+    #[arg(short, long, default_value_t = default_threads())]
+    threads: usize,
+    /// searched per move by the skill levels, which keeps the higher levels
+    /// from searching for too long
+    #[arg(short, long, default_value_t = 20000)]
+    nodes: u64,
+    /// random moves played from the initial position before each game
+    #[arg(long, default_value_t = 6)]
+    random_plies: u32,
+    #[arg(long, default_value_t = 16)]
+    hash: usize,
+    #[arg(short, long)]
+    seed: Option<u64>,
+}
+
+fn default_threads() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Reference {
+    depth: u32,
+    elo: f64,
+}
+
+fn parse_reference(arg: &str) -> Result<Reference, String> {
+    let (depth, elo) = arg.split_once(':').ok_or("expected depth:elo")?;
+    Ok(Reference {
+        depth: depth
+            .parse()
+            .map_err(|_| format!("invalid depth {depth}"))?,
+        elo: elo.parse().map_err(|_| format!("invalid elo {elo}"))?,
+    })
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Player {
+    Skill { level: u32, nodes: u64 },
+    Depth(u32),
+}
+
+impl Player {
+    fn get_limits(&self, rng: &mut Rng) -> SearchLimits {
+        match *self {
+            Self::Skill { level, nodes } => SearchLimits {
+                nodes: Some(nodes),
+                skill: Some(Skill::new(level as f64, rng.next_u64())),
+                ..SearchLimits::default()
+            },
+            Self::Depth(depth) => SearchLimits {
+                depth: Some(depth),
+                ..SearchLimits::default()
+            },
+        }
+    }
+}
+
+// points for white
+fn play_game(
+    mut game: GameState,
+    white: Player,
+    black: Player,
+    tts: &mut [TranspositionTable; 2],
+    rng: &mut Rng,
+) -> f64 {
+    let options = SearchOptions::default();
+    let stop = AtomicBool::new(false);
+    tts.iter_mut().for_each(TranspositionTable::clear);
+    loop {
+        let turn = game.turn();
+        if game.legal_moves().is_empty() {
+            return match (game.is_check(), turn) {
+                (false, _) => 0.5,
+                (true, Color::White) => 0.0,
+                (true, Color::Black) => 1.0,
+            };
+        }
+        if game.is_draw() || game.history().len() >= MAX_GAME_PLIES {
+            return 0.5;
+        }
+        let (player, tt) = match turn {
+            Color::White => (white, &tts[0]),
+            Color::Black => (black, &tts[1]),
+        };
+        let limits = player.get_limits(rng);
+        let result = search(&mut game, tt, &limits, &options, &stop, |_| ());
+        let Some(best_move) = result.best_move else {
+            return 0.5;
+        };
+        // a full strength reference finding a mate won't let it go, and the
+        // weakened side gets to play on until it is actually mated
+        if is_mate_score(result.score) && matches!(player, Player::Depth(_)) {
+            return match (result.score > 0, turn) {
+                (true, Color::White) | (false, Color::Black) => 1.0,
+                _ => 0.0,
+            };
+        }
+        game.make_move(best_move);
+    }
+}
+
+fn expected_score(diff: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-diff / 400.0))
+}
+
+// the rating at which the expected points against every opponent add up to
+// the points actually scored
+fn performance(results: &[(f64, usize, f64)]) -> f64 {
+    let (mut low, mut high) = (MIN_RATING, MAX_RATING);
+    let points: f64 = results.iter().map(|&(_, _, points)| points).sum();
+    for _ in 0..100 {
+        let rating = (low + high) / 2.0;
+        let expected: f64 = results
+            .iter()
+            .map(|&(elo, games, _)| games as f64 * expected_score(rating - elo))
+            .sum();
+        match expected < points {
+            true => low = rating,
+            false => high = rating,
+        }
+    }
+    (low + high) / 2.0
+}
+
+// the rating UCI_Elo currently maps to a level, to compare against
+fn mapped_elo(level: u32) -> f64 {
+    MIN_ELO as f64 + (MAX_ELO - MIN_ELO) as f64 * level as f64 / MAX_SKILL_LEVEL as f64
+}
+
+fn main() {
+    let cli = Cli::parse();
+    if let Some(level) = cli.levels.iter().find(|&&level| level > MAX_SKILL_LEVEL) {
+        return eprintln!("skill level {level} is above {MAX_SKILL_LEVEL}");
+    }
+    let seed = cli.seed.unwrap_or_else(|| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |t| t.as_nanos() as u64)
+    });
+    let pairings: Vec<(usize, usize)> = (0..cli.levels.len())
+        .flat_map(|level| (0..cli.reference.len()).map(move |reference| (level, reference)))
+        .collect();
+    let total = pairings.len() * cli.games;
+    let next_game = AtomicUsize::new(0);
+    // points of each level against each reference
+    let mut points = vec![vec![0.0; cli.reference.len()]; cli.levels.len()];
+    let (sender, receiver) = mpsc::channel::<(usize, usize, f64)>();
+    thread::scope(|scope| {
+        for _ in 0..cli.threads.max(1) {
+            let sender = sender.clone();
+            let (cli, pairings, next_game) = (&cli, &pairings, &next_game);
+            scope.spawn(move || {
+                let mut tts = [
+                    TranspositionTable::new(cli.hash),
+                    TranspositionTable::new(cli.hash),
+                ];
+                loop {
+                    let index = next_game.fetch_add(1, Ordering::Relaxed);
+                    if index >= total {
+                        break;
+                    }
+                    let (level, reference) = pairings[index / cli.games];
+                    let skill = Player::Skill {
+                        level: cli.levels[level],
+                        nodes: cli.nodes,
+                    };
+                    let opponent = Player::Depth(cli.reference[reference].depth);
+                    // both colors play the same opening
+                    let mut rng = Rng::new(seed ^ (index / 2) as u64);
+                    let game = random_opening(&GameState::default(), cli.random_plies, &mut rng)
+                        .unwrap_or_default();
+                    let points = match index % 2 {
+                        0 => play_game(game, skill, opponent, &mut tts, &mut rng),
+                        _ => 1.0 - play_game(game, opponent, skill, &mut tts, &mut rng),
+                    };
+                    if sender.send((level, reference, points)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(sender);
+        for (played, (level, reference, result)) in receiver.into_iter().enumerate() {
+            points[level][reference] += result;
+            eprint!("\r{}/{total} games", played + 1);
+        }
+        eprintln!();
+    });
+    for (level, points) in cli.levels.iter().zip(&points) {
+        let results: Vec<(f64, usize, f64)> = cli
+            .reference
+            .iter()
+            .zip(points)
+            .map(|(reference, &points)| (reference.elo, cli.games, points))
+            .collect();
+        let scores: Vec<String> = cli
+            .reference
+            .iter()
+            .zip(points)
+            .map(|(reference, points)| format!("depth {}: {points}/{}", reference.depth, cli.games))
+            .collect();
+        println!(
+            "level {level:>2}: {}, rating {:.0}, UCI_Elo {:.0}",
+            scores.join(", "),
+            performance(&results),
+            mapped_elo(*level)
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_reference() {
+        assert_eq!(
+            parse_reference("4:1850"),
+            Ok(Reference {
+                depth: 4,
+                elo: 1850.0
+            })
+        );
+        assert!(parse_reference("4").is_err());
+        assert!(parse_reference("four:1850").is_err());
+    }
+
+    #[test]
+    fn test_performance() {
+        assert!((performance(&[(1500.0, 10, 5.0)]) - 1500.0).abs() < 0.1);
+        let rating = performance(&[(1500.0, 100, 76.0)]);
+        assert!((1690.0..1710.0).contains(&rating), "{rating}");
+        // scoring evenly against a stronger and a weaker opponent
+        let rating = performance(&[(1300.0, 10, 8.0), (1700.0, 10, 2.0)]);
+        assert!((rating - 1500.0).abs() < 0.1);
+        // a clean sweep has no finite rating
+        assert!(performance(&[(1500.0, 10, 10.0)]) > MAX_RATING - 1.0);
+        assert_eq!(mapped_elo(MAX_SKILL_LEVEL), MAX_ELO as f64);
+    }
+
+    #[test]
+    fn test_play_game() {
+        let mut tts = [TranspositionTable::new(1), TranspositionTable::new(1)];
+        let mut rng = Rng::new(3);
+        let game = GameState::try_from_fen("7k/8/6K1/8/8/8/8/1Q6 w - - 0 1").unwrap();
+        let skill = Player::Skill {
+            level: MAX_SKILL_LEVEL,
+            nodes: 2000,
+        };
+        assert_eq!(
+            play_game(game, skill, Player::Depth(1), &mut tts, &mut rng),
+            1.0
+        );
+        let game = GameState::try_from_fen("7k/8/6K1/8/8/8/8/1q6 w - - 0 1").unwrap();
+        assert_eq!(
+            play_game(game, skill, Player::Depth(3), &mut tts, &mut rng),
+            0.0
+        );
+    }
+}
@@ -0,0 +1,66 @@
+// helpers shared by the datagen and calibrate bins
+use rust_chess::GameState;
+
+const MAX_OPENING_TRIES: u32 = 1000;
+
+// xorshift64*, good enough for picking opening moves and seeds
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+// plays random moves from a start position, trying again whenever the game
+// ends before they are done. None when every try ended early, such as from
+// a position that is already over.
+pub fn random_opening(start: &GameState, plies: u32, rng: &mut Rng) -> Option<GameState> {
+    'retry: for _ in 0..MAX_OPENING_TRIES {
+        let mut game = start.clone();
+        for _ in 0..plies {
+            let moves = game.legal_moves();
+            if moves.is_empty() || game.is_draw() {
+                continue 'retry;
+            }
+            game.make_move(moves[rng.below(moves.len())]);
+        }
+        if !game.legal_moves().is_empty() {
+            return Some(game);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_random_opening() {
+        let mut rng = Rng::new(7);
+        let game = random_opening(&GameState::default(), 8, &mut rng).unwrap();
+        assert_eq!(game.history().len(), 8);
+        assert!(!game.legal_moves().is_empty());
+        let mut rng = Rng::new(7);
+        let again = random_opening(&GameState::default(), 8, &mut rng).unwrap();
+        assert_eq!(game.to_fen(), again.to_fen());
+        assert!((0..100).all(|_| rng.below(3) < 3));
+        // a finished game has no opening to play
+        let stalemate = GameState::try_from_fen("k7/2Q5/1K6/8/8/8/8/8 b - - 0 1").unwrap();
+        assert!(random_opening(&stalemate, 0, &mut rng).is_none());
+        assert!(random_opening(&stalemate, 4, &mut rng).is_none());
+    }
+}
@@ -0,0 +1,262 @@
+mod common;
+
+use clap::Parser;
+use common::{random_opening, Rng};
+use rust_chess::{
+    nnue::Network,
+    search::{is_mate_score, is_tactical, search, SearchLimits, SearchOptions, TranspositionTable},
+    GameResult, GameState, PackedRecord,
+};
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+#[cfg(feature = "jemalloc")]
+#[global_allocator]
+static GLOBAL: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
+
+const MAX_GAME_PLIES: usize = 400;
+const REPORT_INTERVAL: u64 = 100;
+
+#[derive(Parser)]
+#[command(author, version, about, long_about = None)]
+struct Cli {
+    /// packed positions, each followed by the score for white and the result
+    output: PathBuf,
+    #[arg(short, long, default_value_t = 1000)]
+    games: u64,
+    #[arg(short, long, default_value_t = default_threads())]
+    threads: usize,
+    /// searched per move
+    #[arg(short, long, default_value_t = 5000)]
+    nodes: u64,
+    /// random legal moves played before recording starts
+    #[arg(short, long, default_value_t = 8)]
+    random_plies: u32,
+    /// openings to start from instead of the initial position, one epd per line
+    #[arg(short, long)]
+    book: Option<PathBuf>,
+    /// searches with a network instead of the hand-crafted evaluation
+    #[arg(short, long)]
+    eval_file: Option<PathBuf>,
+    #[arg(long, default_value_t = 16)]
+    hash: usize,
+    #[arg(short, long)]
+    seed: Option<u64>,
+}
+
+fn default_threads() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+fn load_book(path: &PathBuf) -> Result<(Vec<GameState>, usize), String> {
+    let text = fs::read_to_string(path).map_err(|err| err.to_string())?;
+    Ok(parse_book(&text))
+}
+
+// the positions of a book of fens or epds and how many lines weren't one or
+// held a game that is already over, blank lines and comments aside
+fn parse_book(text: &str) -> (Vec<GameState>, usize) {
+    let mut skipped = 0;
+    let book = text
+        .lines()
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().take(4).collect();
+            let game = GameState::try_from_fen(&format!("{} 0 1", fields.join(" ")))
+                .ok()
+                .filter(|game| !game.legal_moves().is_empty() && !game.is_draw());
+            skipped += game.is_none() as usize;
+            game
+        })
+        .collect();
+    (book, skipped)
+}
+
+fn play_game(
+    mut game: GameState,
+    tt: &mut TranspositionTable,
+    limits: &SearchLimits,
+    stop: &AtomicBool,
+) -> Vec<PackedRecord> {
+    let options = SearchOptions::default();
+    let mut records = Vec::new();
+    tt.clear();
+    let result = loop {
+        if game.legal_moves().is_empty() {
+            break match game.is_check() {
+                true => GameResult::win_for(!game.turn()),
+                false => GameResult::Draw,
+            };
+        }
+        if game.is_draw() || game.history().len() >= MAX_GAME_PLIES {
+            break GameResult::Draw;
+        }
+        let result = search(&mut game, tt, limits, &options, stop, |_| ());
+        let Some(best_move) = result.best_move else {
+            break GameResult::Draw;
+        };
+        // the search already knows how the game ends
+        if is_mate_score(result.score) {
+            break match result.score > 0 {
+                true => GameResult::win_for(game.turn()),
+                false => GameResult::win_for(!game.turn()),
+            };
+        }
+        // quiet positions only, the score of anything else depends on the
+        // tactics still to be played out
+        if !game.is_check() && !is_tactical(&game, best_move) {
+            records.push(PackedRecord::new(&game, result.score, GameResult::Draw));
+        }
+        game.make_move(best_move);
+    };
+    for record in &mut records {
+        record.result = result;
+    }
+    records
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let book = match &cli.book {
+        Some(path) => match load_book(path) {
+            Ok((book, skipped)) if !book.is_empty() => {
+                if skipped > 0 {
+                    eprintln!(
+                        "skipped {skipped} invalid or finished lines in {}",
+                        path.display()
+                    );
+                }
+                book
+            }
+            Ok(_) => return eprintln!("no positions in {}", path.display()),
+            Err(err) => return eprintln!("could not read {}: {err}", path.display()),
+        },
+        None => vec![GameState::default()],
+    };
+    let network = match &cli.eval_file {
+        Some(path) => match Network::load(path) {
+            Ok(network) => Some(Arc::new(network)),
+            Err(err) => return eprintln!("could not load {}: {err}", path.display()),
+        },
+        None => None,
+    };
+    let mut output = match File::create(&cli.output) {
+        Ok(file) => BufWriter::new(file),
+        Err(err) => return eprintln!("could not create {}: {err}", cli.output.display()),
+    };
+    let seed = cli.seed.unwrap_or_else(|| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |t| t.as_nanos() as u64)
+    });
+    let limits = SearchLimits {
+        nodes: Some(cli.nodes),
+        ..SearchLimits::default()
+    };
+    let next_game = AtomicU64::new(0);
+    let stop = AtomicBool::new(false);
+    let start = Instant::now();
+    let (sender, receiver) = mpsc::channel::<Vec<PackedRecord>>();
+    thread::scope(|scope| {
+        for _ in 0..cli.threads.max(1) {
+            let sender = sender.clone();
+            let (book, network, limits) = (&book, &network, &limits);
+            let (next_game, stop) = (&next_game, &stop);
+            let (plies, hash) = (cli.random_plies, cli.hash);
+            scope.spawn(move || {
+                let mut tt = TranspositionTable::new(hash);
+                loop {
+                    let index = next_game.fetch_add(1, Ordering::Relaxed);
+                    if index >= cli.games {
+                        break;
+                    }
+                    // every game gets its own stream so runs can be repeated
+                    let mut rng = Rng::new(seed ^ index);
+                    let mut game = book[rng.below(book.len())].clone();
+                    game.set_network(network.clone());
+                    let Some(game) = random_opening(&game, plies, &mut rng) else {
+                        continue;
+                    };
+                    if sender.send(play_game(game, &mut tt, limits, stop)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(sender);
+        let (mut games, mut positions) = (0, 0);
+        for records in receiver {
+            for record in &records {
+                if let Err(err) = output.write_all(&record.to_bytes()) {
+                    eprintln!("could not write {}: {err}", cli.output.display());
+                    stop.store(true, Ordering::Relaxed);
+                    next_game.store(cli.games, Ordering::Relaxed);
+                    return;
+                }
+            }
+            games += 1;
+            positions += records.len();
+            if games % REPORT_INTERVAL == 0 {
+                let elapsed = start.elapsed().as_secs_f64();
+                eprintln!(
+                    "{games}/{} games, {positions} positions, {:.0} positions/s",
+                    cli.games,
+                    positions as f64 / elapsed
+                );
+            }
+        }
+        eprintln!(
+            "{games} games, {positions} positions in {:.1}s",
+            start.elapsed().as_secs_f64()
+        );
+    });
+    if let Err(err) = output.flush() {
+        eprintln!("could not write {}: {err}", cli.output.display());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_book() {
+        let text = "# openings\n\
+            rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - c0 \"e4\";\n\
+            \n\
+            rnbqkbnrr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -\n\
+            not a fen\n\
+            k7/2Q5/1K6/8/8/8/8/8 b - -\n\
+            8/8/8/4k3/8/8/8/4K3 w - -\n\
+            rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1\n";
+        let (book, skipped) = parse_book(text);
+        assert_eq!(book.len(), 2);
+        assert_eq!(book[1].to_fen(), GameState::default().to_fen());
+        // the stalemate and the bare kings would never give an opening
+        assert_eq!(skipped, 4);
+    }
+
+    #[test]
+    fn test_play_game() {
+        let game = GameState::try_from_fen("7k/8/6K1/8/8/8/8/1Q6 w - - 0 1").unwrap();
+        let mut tt = TranspositionTable::new(1);
+        let limits = SearchLimits {
+            nodes: Some(2000),
+            ..SearchLimits::default()
+        };
+        let records = play_game(game, &mut tt, &limits, &AtomicBool::new(false));
+        assert!(records.iter().all(|r| r.result == GameResult::WhiteWin));
+        let game = GameState::try_from_fen("7k/8/6K1/8/8/8/8/8 w - - 0 1").unwrap();
+        let records = play_game(game, &mut tt, &limits, &AtomicBool::new(false));
+        assert!(records.is_empty());
+    }
+}
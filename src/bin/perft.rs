@@ -1,13 +1,17 @@
 use clap::Parser;
-use rust_chess::GameState;
-use std::{cell::OnceCell, collections::HashMap, hash::Hash, time::Instant};
+use rust_chess::{search::TranspositionTable, GameState};
+use std::{collections::HashMap, sync::LazyLock, time::Instant};
+
+#[cfg(feature = "jemalloc")]
+#[global_allocator]
+static GLOBAL: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
 
 type Depth = u32;
 type ScenarioId = u32;
 type Perft = u128;
 
-const FEN_MAP: LazyMap<ScenarioId, &'static str> = LazyMap::new(init_fen_map);
-const PERFT_MAP: LazyMap<(ScenarioId, Depth), Perft> = LazyMap::new(init_perft_map);
+static FEN_MAP: LazyLock<HashMap<ScenarioId, &'static str>> = LazyLock::new(init_fen_map);
+static PERFT_MAP: LazyLock<HashMap<(ScenarioId, Depth), Perft>> = LazyLock::new(init_perft_map);
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
@@ -16,27 +20,9 @@ struct Cli {
     scenario: ScenarioId,
     #[arg(short, long)]
     depth: Depth,
-}
-
-struct LazyMap<K, V> {
-    inner: OnceCell<HashMap<K, V>>,
-    func: fn() -> HashMap<K, V>,
-}
-
-impl<K, V> LazyMap<K, V>
-where
-    K: Hash + Eq,
-{
-    const fn new(func: fn() -> HashMap<K, V>) -> Self {
-        Self {
-            inner: OnceCell::new(),
-            func,
-        }
-    }
-
-    fn get(&self, key: &K) -> Option<&V> {
-        self.inner.get_or_init(self.func).get(key)
-    }
+    /// megabytes of transposition table to hash the counts in
+    #[arg(long)]
+    hash: Option<usize>,
 }
 
 fn init_perft_map() -> HashMap<(ScenarioId, Depth), Perft> {
@@ -44,10 +30,17 @@ fn init_perft_map() -> HashMap<(ScenarioId, Depth), Perft> {
         ((1, 3), 8_902),
         ((1, 4), 197_281),
         ((1, 5), 4_865_609),
+        ((2, 3), 97_862),
+        ((2, 4), 4_085_603),
         ((3, 2), 191),
         ((3, 3), 2_812),
+        ((3, 4), 43_238),
+        ((3, 5), 674_624),
+        ((4, 3), 9_467),
+        ((4, 4), 422_333),
         ((5, 2), 1_486),
         ((5, 3), 62_379),
+        ((5, 4), 2_103_487),
     ])
 }
 
@@ -62,20 +55,40 @@ fn init_fen_map() -> HashMap<ScenarioId, &'static str> {
     ])
 }
 
-fn calc_perft(fen: &str, depth: Depth) -> Result<u128, &'static str> {
+fn calc_perft(
+    fen: &str,
+    depth: Depth,
+    tt: Option<&TranspositionTable>,
+) -> Result<u128, &'static str> {
     let mut gs = GameState::try_from_fen(fen)?;
-    let perft = gs.perft(depth);
+    let perft = match tt {
+        Some(tt) => tt.perft(&mut gs, depth),
+        None => gs.perft(depth),
+    };
     Ok(perft)
 }
 
-fn calc_scenario_perft(scenario: ScenarioId, depth: Depth) -> Result<u128, &'static str> {
-    calc_perft(FEN_MAP.get(&scenario).ok_or("couldn't find fen")?, depth)
+fn calc_scenario_perft(
+    scenario: ScenarioId,
+    depth: Depth,
+    tt: Option<&TranspositionTable>,
+) -> Result<u128, &'static str> {
+    calc_perft(
+        FEN_MAP.get(&scenario).ok_or("couldn't find fen")?,
+        depth,
+        tt,
+    )
 }
 
 fn main() {
-    let Cli { scenario, depth } = Cli::parse();
+    let Cli {
+        scenario,
+        depth,
+        hash,
+    } = Cli::parse();
+    let tt = hash.map(TranspositionTable::new);
     let start = Instant::now();
-    let perft = calc_scenario_perft(scenario, depth).unwrap();
+    let perft = calc_scenario_perft(scenario, depth, tt.as_ref()).unwrap();
     println!("Elapsed time: {:.2?}", start.elapsed());
     println!("Calculated perft: {perft}");
     if let Some(&ref_perft) = PERFT_MAP.get(&(scenario, depth)) {
@@ -89,18 +102,42 @@ mod tests {
     use super::*;
 
     fn test_pos(scenario: ScenarioId, depth: Depth) {
-        let perft = calc_scenario_perft(scenario, depth).unwrap();
+        let perft = calc_scenario_perft(scenario, depth, None).unwrap();
         let ref_perft = *PERFT_MAP.get(&(scenario, depth)).unwrap();
         assert_eq!(perft, ref_perft);
     }
 
+    #[test]
+    fn test_hashed() {
+        let tt = TranspositionTable::new(1);
+        for (scenario, depth) in [(1, 4), (2, 3), (3, 4), (4, 3), (5, 3)] {
+            let perft = calc_scenario_perft(scenario, depth, Some(&tt)).unwrap();
+            assert_eq!(perft, PERFT_MAP[&(scenario, depth)], "scenario {scenario}");
+        }
+    }
+
     #[test]
     fn test_pos_1() {
         test_pos(1, 4)
     }
 
+    #[test]
+    fn test_pos_2() {
+        test_pos(2, 3)
+    }
+
     #[test]
     fn test_pos_3() {
         test_pos(3, 2)
     }
+
+    #[test]
+    fn test_pos_4() {
+        test_pos(4, 3)
+    }
+
+    #[test]
+    fn test_pos_5() {
+        test_pos(5, 3)
+    }
 }
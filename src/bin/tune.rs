@@ -0,0 +1,656 @@
+use clap::Parser;
+use rust_chess::{
+    eval::{self, EvalParams, Score, DEFAULT_PARAMS, MAX_PHASE},
+    search, Color, GameState, PackedRecord, RECORD_BYTES,
+};
+use std::{
+    fs,
+    ops::{Add, AddAssign, Mul, Sub},
+    path::PathBuf,
+    thread,
+    time::Instant,
+};
+
+#[cfg(feature = "jemalloc")]
+#[global_allocator]
+static GLOBAL: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
+
+const REPORT_INTERVAL: u32 = 25;
+const BETA1: f64 = 0.9;
+const BETA2: f64 = 0.999;
+const EPSILON: f64 = 1e-8;
+const PST_NAMES: [&str; 6] = ["pawn", "rook", "knight", "bishop", "queen", "king"];
+
+#[derive(Parser)]
+#[command(author, version, about, long_about = None)]
+struct Cli {
+    /// one position per line, a fen or epd followed by the game result
+    /// (1-0, 0-1, 1/2-1/2, 1.0, 0.5, 0.0) or a score in centipawns for white,
+    /// or the binary output of datagen
+    dataset: PathBuf,
+    /// the dataset was written by datagen
+    #[arg(short, long)]
+    packed: bool,
+    /// fits packed positions to their search scores instead of the results
+    #[arg(short, long, requires = "packed")]
+    scores: bool,
+    #[arg(short, long, default_value = "params.rs")]
+    output: PathBuf,
+    #[arg(short, long, default_value_t = default_threads())]
+    threads: usize,
+    #[arg(short, long, default_value_t = 500)]
+    epochs: u32,
+    #[arg(short, long, default_value_t = 1.0)]
+    learning_rate: f64,
+    /// the sigmoid scaling constant, fitted to the dataset when left out
+    #[arg(short, long)]
+    k: Option<f64>,
+    /// reads at most this many positions
+    #[arg(long)]
+    limit: Option<usize>,
+}
+
+fn default_threads() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+// labels are from white's point of view
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Label {
+    Result(f32),
+    Score(i32),
+}
+
+impl Label {
+    fn target(self, k: f64) -> f64 {
+        match self {
+            Self::Result(result) => result as f64,
+            Self::Score(score) => sigmoid(k, score as f64),
+        }
+    }
+}
+
+// sparse weight counts, white's minus black's, sorted by parameter index
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct Coefficients(Vec<(u16, i32)>);
+
+impl Add for Coefficients {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self::Output {
+        let mut merged = Vec::with_capacity(self.0.len() + rhs.0.len());
+        let (mut lhs, mut rhs) = (self.0.into_iter().peekable(), rhs.0.into_iter().peekable());
+        loop {
+            let next = match (lhs.peek(), rhs.peek()) {
+                (Some(&(i, a)), Some(&(j, b))) if i == j => {
+                    lhs.next();
+                    rhs.next();
+                    (i, a + b)
+                }
+                (Some(&(i, _)), Some(&(j, _))) if i < j => lhs.next().unwrap(),
+                (_, Some(_)) => rhs.next().unwrap(),
+                (Some(_), None) => lhs.next().unwrap(),
+                (None, None) => break,
+            };
+            if next.1 != 0 {
+                merged.push(next);
+            }
+        }
+        Self(merged)
+    }
+}
+
+impl AddAssign for Coefficients {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = core::mem::take(self) + rhs;
+    }
+}
+
+impl Sub for Coefficients {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self::Output {
+        self + rhs * -1
+    }
+}
+
+impl Mul<i32> for Coefficients {
+    type Output = Self;
+    fn mul(self, rhs: i32) -> Self::Output {
+        match rhs {
+            0 => Self::default(),
+            _ => Self(self.0.into_iter().map(|(i, c)| (i, c * rhs)).collect()),
+        }
+    }
+}
+
+// every parameter replaced by its own basis vector, evaluating with these
+// gives the coefficient of each weight
+fn unit_params() -> EvalParams<Coefficients> {
+    let mut index = 0;
+    DEFAULT_PARAMS.map(|_| {
+        let unit = Coefficients(vec![(index, 1)]);
+        index += 1;
+        unit
+    })
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Entry {
+    start: u32,
+    len: u16,
+    phase: u8,
+    label: Label,
+}
+
+// the evaluation is linear in its weights, so a position only needs to be
+// kept as its coefficients and phase
+#[derive(Debug, Default)]
+struct Dataset {
+    coefficients: Vec<(u16, i16)>,
+    entries: Vec<Entry>,
+}
+
+impl Dataset {
+    fn push(&mut self, mut game: GameState, label: Label, units: &EvalParams<Coefficients>) {
+        // plays captures until the position is quiet, so the static
+        // evaluation the tuner fits isn't thrown off by hanging pieces
+        let (_, pv) = search::quiesce_pv(&mut game);
+        for move_ in pv {
+            game.make_move(move_);
+        }
+        // mated, or still in check when the search gave up
+        if game.is_check() {
+            return;
+        }
+        let board = game.board();
+        let tempo = match game.turn() {
+            Color::White => units.tempo.clone(),
+            Color::Black => units.tempo.clone() * -1,
+        };
+        let coefficients = eval::evaluate_terms(board, Color::White, units)
+            - eval::evaluate_terms(board, Color::Black, units)
+            + tempo;
+        let start = u32::try_from(self.coefficients.len()).expect("dataset too large");
+        self.entries.push(Entry {
+            start,
+            len: coefficients.0.len() as u16,
+            phase: eval::get_phase(board) as u8,
+            label,
+        });
+        let clamp = |c: i32| c.clamp(i16::MIN as i32, i16::MAX as i32) as i16;
+        self.coefficients
+            .extend(coefficients.0.into_iter().map(|(i, c)| (i, clamp(c))));
+    }
+
+    fn append(&mut self, other: Self) {
+        let offset = self.coefficients.len() as u32;
+        self.coefficients.extend(other.coefficients);
+        self.entries
+            .extend(other.entries.into_iter().map(|entry| Entry {
+                start: entry.start + offset,
+                ..entry
+            }));
+    }
+
+    // white's score with the current weights, as (mg, eg) pairs
+    fn evaluate(&self, entry: &Entry, weights: &[[f64; 2]]) -> f64 {
+        let start = entry.start as usize;
+        let coefficients = &self.coefficients[start..start + entry.len as usize];
+        let (mg, eg) = coefficients.iter().fold((0.0, 0.0), |(mg, eg), &(i, c)| {
+            let weight = weights[i as usize];
+            (mg + c as f64 * weight[0], eg + c as f64 * weight[1])
+        });
+        let phase = entry.phase as f64;
+        let max_phase = MAX_PHASE as f64;
+        (mg * phase + eg * (max_phase - phase)) / max_phase
+    }
+}
+
+fn sigmoid(k: f64, score: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-k * score / 400.0))
+}
+
+fn parse_label(token: &str) -> Option<Label> {
+    let token = token.trim_matches(|c| matches!(c, '[' | ']' | '"' | ';'));
+    let label = match token {
+        "1-0" | "1.0" => Label::Result(1.0),
+        "0-1" | "0.0" => Label::Result(0.0),
+        "1/2-1/2" | "0.5" => Label::Result(0.5),
+        score => Label::Score(score.parse().ok()?),
+    };
+    Some(label)
+}
+
+// the four position fields of the fen, then optional clocks, then anything
+// else such as epd opcodes, with the label last
+fn parse_line(line: &str) -> Option<(GameState, Label)> {
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    if tokens.len() < 5 || line.starts_with('#') {
+        return None;
+    }
+    let (label, rest) = tokens[4..].split_last()?;
+    let clocks = rest
+        .iter()
+        .copied()
+        .take_while(|t| t.parse::<u16>().is_ok())
+        .take(2);
+    let fen: Vec<&str> = tokens[..4].iter().copied().chain(clocks).collect();
+    let game = GameState::try_from_fen(&fen.join(" ")).ok()?;
+    Some((game, parse_label(label)?))
+}
+
+fn parse_record(bytes: &[u8], scores: bool) -> Option<(GameState, Label)> {
+    let record = PackedRecord::from_bytes(bytes.try_into().ok()?).ok()?;
+    let label = match scores {
+        true => Label::Score(record.score as i32),
+        false => Label::Result(record.result.get_points()),
+    };
+    Some((record.get_game().ok()?, label))
+}
+
+fn load<T: Sync>(
+    items: &[T],
+    threads: usize,
+    parse: impl Fn(&T) -> Option<(GameState, Label)> + Sync,
+) -> Dataset {
+    let units = unit_params();
+    let chunk_len = items.len().div_ceil(threads).max(1);
+    thread::scope(|scope| {
+        let handles: Vec<_> = items
+            .chunks(chunk_len)
+            .map(|chunk| {
+                let (units, parse) = (&units, &parse);
+                scope.spawn(move || {
+                    let mut dataset = Dataset::default();
+                    for (game, label) in chunk.iter().filter_map(parse) {
+                        dataset.push(game, label, units);
+                    }
+                    dataset
+                })
+            })
+            .collect();
+        let mut dataset = Dataset::default();
+        for handle in handles {
+            dataset.append(handle.join().unwrap());
+        }
+        dataset
+    })
+}
+
+// splits the entries across threads and sums what `f` returns for each chunk
+fn parallel_sum<T: Send>(
+    dataset: &Dataset,
+    threads: usize,
+    f: impl Fn(&[Entry]) -> T + Sync,
+    sum: impl Fn(T, T) -> T,
+) -> Option<T> {
+    let chunk_len = dataset.entries.len().div_ceil(threads).max(1);
+    thread::scope(|scope| {
+        let handles: Vec<_> = dataset
+            .entries
+            .chunks(chunk_len)
+            .map(|chunk| scope.spawn(|| f(chunk)))
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).reduce(sum)
+    })
+}
+
+fn mean_error(dataset: &Dataset, weights: &[[f64; 2]], k: f64, threads: usize) -> f64 {
+    let total = parallel_sum(
+        dataset,
+        threads,
+        |entries| {
+            entries
+                .iter()
+                .map(|entry| {
+                    let error =
+                        entry.label.target(k) - sigmoid(k, dataset.evaluate(entry, weights));
+                    error * error
+                })
+                .sum::<f64>()
+        },
+        |a, b| a + b,
+    );
+    total.unwrap_or(0.0) / dataset.entries.len().max(1) as f64
+}
+
+// golden section search for the scaling constant that best matches the
+// current weights to the labels
+fn fit_k(dataset: &Dataset, weights: &[[f64; 2]], threads: usize) -> f64 {
+    let ratio = (5f64.sqrt() - 1.0) / 2.0;
+    let (mut low, mut high) = (0.01, 10.0);
+    for _ in 0..50 {
+        let a = high - ratio * (high - low);
+        let b = low + ratio * (high - low);
+        match mean_error(dataset, weights, a, threads) < mean_error(dataset, weights, b, threads) {
+            true => high = b,
+            false => low = a,
+        }
+    }
+    (low + high) / 2.0
+}
+
+fn gradient(dataset: &Dataset, weights: &[[f64; 2]], k: f64, threads: usize) -> Vec<[f64; 2]> {
+    let scale = k * 10f64.ln() / 400.0;
+    let total = parallel_sum(
+        dataset,
+        threads,
+        |entries| {
+            let mut gradient = vec![[0.0; 2]; weights.len()];
+            for entry in entries {
+                let predicted = sigmoid(k, dataset.evaluate(entry, weights));
+                let error = predicted - entry.label.target(k);
+                let slope = 2.0 * error * predicted * (1.0 - predicted) * scale;
+                let mg = slope * entry.phase as f64 / MAX_PHASE as f64;
+                let eg = slope - mg;
+                let start = entry.start as usize;
+                for &(i, c) in &dataset.coefficients[start..start + entry.len as usize] {
+                    gradient[i as usize][0] += mg * c as f64;
+                    gradient[i as usize][1] += eg * c as f64;
+                }
+            }
+            gradient
+        },
+        |mut a, b| {
+            a.iter_mut().zip(b).for_each(|(a, b)| {
+                a[0] += b[0];
+                a[1] += b[1];
+            });
+            a
+        },
+    );
+    let n = dataset.entries.len().max(1) as f64;
+    let mut total = total.unwrap_or_else(|| vec![[0.0; 2]; weights.len()]);
+    total.iter_mut().flatten().for_each(|g| *g /= n);
+    total
+}
+
+fn to_params(weights: &[[f64; 2]]) -> EvalParams {
+    let mut weights = weights.iter();
+    DEFAULT_PARAMS.map(|_| {
+        let [mg, eg] = weights.next().unwrap();
+        Score::new(mg.round() as i32, eg.round() as i32)
+    })
+}
+
+fn format_scores(scores: &[Score]) -> String {
+    let scores: Vec<String> = scores
+        .iter()
+        .map(|score| format!("s({}, {})", score.mg, score.eg))
+        .collect();
+    scores.join(", ")
+}
+
+// matches the layout of src/eval/params.rs so the output can replace it
+fn write_params(params: &EvalParams) -> String {
+    let mut out = String::new();
+    out.push_str("// can be regenerated with the tune binary\n");
+    out.push_str("use super::{EvalParams, Score};\n\n");
+    out.push_str("const fn s(mg: i32, eg: i32) -> Score {\n    Score::new(mg, eg)\n}\n\n");
+    out.push_str(
+        "// arrays over figures are in `Figure` order: pawn, rook, knight, bishop, queen, king\n",
+    );
+    out.push_str(
+        "// piece-square tables are from white's side with a1 first, black mirrors the rows\n",
+    );
+    out.push_str("#[rustfmt::skip]\npub const DEFAULT_PARAMS: EvalParams = EvalParams {\n");
+    out.push_str(&format!(
+        "    material: [{}],\n",
+        format_scores(&params.material)
+    ));
+    out.push_str("    pst: [\n");
+    for (name, table) in PST_NAMES.iter().zip(&params.pst) {
+        out.push_str(&format!("        // {name}\n        [\n"));
+        for row in table.chunks(8) {
+            out.push_str(&format!("            {},\n", format_scores(row)));
+        }
+        out.push_str("        ],\n");
+    }
+    out.push_str("    ],\n");
+    out.push_str(&format!(
+        "    mobility: [{}],\n",
+        format_scores(&params.mobility)
+    ));
+    for (name, score) in [
+        ("doubled_pawn", params.doubled_pawn),
+        ("isolated_pawn", params.isolated_pawn),
+        ("connected_pawn", params.connected_pawn),
+    ] {
+        out.push_str(&format!("    {name}: {},\n", format_scores(&[score])));
+    }
+    out.push_str(&format!(
+        "    passed_pawn: [{}],\n",
+        format_scores(&params.passed_pawn)
+    ));
+    out.push_str(&format!(
+        "    king_attack: [{}],\n",
+        format_scores(&params.king_attack)
+    ));
+    for (name, score) in [
+        ("pawn_shield", params.pawn_shield),
+        ("bishop_pair", params.bishop_pair),
+        ("rook_open_file", params.rook_open_file),
+        ("rook_semi_open_file", params.rook_semi_open_file),
+        ("tempo", params.tempo),
+    ] {
+        out.push_str(&format!("    {name}: {},\n", format_scores(&[score])));
+    }
+    out.push_str("};\n");
+    out
+}
+
+fn tune(cli: &Cli, dataset: &Dataset) -> EvalParams {
+    let threads = cli.threads.max(1);
+    let mut weights: Vec<[f64; 2]> = DEFAULT_PARAMS
+        .to_vec()
+        .into_iter()
+        .map(|score| [score.mg as f64, score.eg as f64])
+        .collect();
+    let k = cli.k.unwrap_or_else(|| fit_k(dataset, &weights, threads));
+    eprintln!(
+        "K = {k:.4}, error {:.6}",
+        mean_error(dataset, &weights, k, threads)
+    );
+
+    // adam
+    let mut momentum = vec![[0.0; 2]; weights.len()];
+    let mut velocity = vec![[0.0; 2]; weights.len()];
+    for epoch in 1..=cli.epochs {
+        let gradient = gradient(dataset, &weights, k, threads);
+        let correction1 = 1.0 - BETA1.powi(epoch as i32);
+        let correction2 = 1.0 - BETA2.powi(epoch as i32);
+        for i in 0..weights.len() {
+            for phase in 0..2 {
+                let g = gradient[i][phase];
+                momentum[i][phase] = BETA1 * momentum[i][phase] + (1.0 - BETA1) * g;
+                velocity[i][phase] = BETA2 * velocity[i][phase] + (1.0 - BETA2) * g * g;
+                let m = momentum[i][phase] / correction1;
+                let v = velocity[i][phase] / correction2;
+                weights[i][phase] -= cli.learning_rate * m / (v.sqrt() + EPSILON);
+            }
+        }
+        if epoch % REPORT_INTERVAL == 0 || epoch == cli.epochs {
+            let error = mean_error(dataset, &weights, k, threads);
+            eprintln!("epoch {epoch}: error {error:.6}");
+            if let Err(err) = fs::write(&cli.output, write_params(&to_params(&weights))) {
+                eprintln!("could not write {}: {err}", cli.output.display());
+            }
+        }
+    }
+    to_params(&weights)
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let bytes = match fs::read(&cli.dataset) {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            eprintln!("could not read {}: {err}", cli.dataset.display());
+            return;
+        }
+    };
+    let limit = cli.limit.unwrap_or(usize::MAX);
+    let threads = cli.threads.max(1);
+    let start = Instant::now();
+    let dataset = match cli.packed {
+        true => {
+            let records: Vec<&[u8]> = bytes.chunks_exact(RECORD_BYTES).take(limit).collect();
+            load(&records, threads, |record| parse_record(record, cli.scores))
+        }
+        false => {
+            let text = String::from_utf8_lossy(&bytes);
+            let lines: Vec<&str> = text.lines().take(limit).collect();
+            load(&lines, threads, |line| parse_line(line))
+        }
+    };
+    drop(bytes);
+    eprintln!(
+        "loaded {} positions in {:.1}s",
+        dataset.entries.len(),
+        start.elapsed().as_secs_f64()
+    );
+    let params = tune(&cli, &dataset);
+    if let Err(err) = fs::write(&cli.output, write_params(&params)) {
+        eprintln!("could not write {}: {err}", cli.output.display());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_chess::{GameResult, PACKED_BYTES};
+
+    #[test]
+    fn test_parse_line() {
+        let (game, label) = parse_line("4k3/8/8/8/8/8/4P3/4K3 w - - [1.0]").unwrap();
+        assert_eq!(game.to_fen().as_ref(), "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1");
+        assert_eq!(label, Label::Result(1.0));
+        let (game, label) = parse_line("4k3/8/8/8/8/8/4P3/4K3 b - - 3 40 1/2-1/2").unwrap();
+        assert_eq!(game.halfmove_clock(), 3);
+        assert_eq!(label, Label::Result(0.5));
+        let (_, label) = parse_line("4k3/8/8/8/8/8/4P3/4K3 w - - c9 \"0-1\";").unwrap();
+        assert_eq!(label, Label::Result(0.0));
+        let (_, label) = parse_line("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1 -35").unwrap();
+        assert_eq!(label, Label::Score(-35));
+        assert!(parse_line("4k3/8/8/8/8/8/4P3/4K3 w - -").is_none());
+        assert!(parse_line("# comment").is_none());
+        assert!(parse_line("4k3/8/8/8/8/8/4P3/4K3 w - - draw").is_none());
+    }
+
+    #[test]
+    fn test_parse_record() {
+        let game = GameState::try_from_fen("4k3/8/8/8/8/8/4P3/4K3 b - - 0 1").unwrap();
+        let record = PackedRecord::new(&game, 100, GameResult::BlackWin).to_bytes();
+        let (decoded, label) = parse_record(&record, false).unwrap();
+        assert_eq!(decoded.to_fen(), game.to_fen());
+        assert_eq!(label, Label::Result(0.0));
+        let (_, label) = parse_record(&record, true).unwrap();
+        assert_eq!(label, Label::Score(-100));
+        assert!(parse_record(&record[..PACKED_BYTES], false).is_none());
+    }
+
+    #[test]
+    fn test_coefficients_match_evaluation() {
+        let units = unit_params();
+        let weights: Vec<[f64; 2]> = DEFAULT_PARAMS
+            .to_vec()
+            .into_iter()
+            .map(|score| [score.mg as f64, score.eg as f64])
+            .collect();
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R b KQ - 1 8",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        ] {
+            let game = GameState::try_from_fen(fen).unwrap();
+            let mut dataset = Dataset::default();
+            dataset.push(game.clone(), Label::Result(0.5), &units);
+            let entry = dataset.entries[0];
+            let mut leaf = game.clone();
+            let (_, pv) = search::quiesce_pv(&mut leaf);
+            for move_ in pv {
+                leaf.make_move(move_);
+            }
+            let expected = match leaf.turn() {
+                Color::White => eval::evaluate(&leaf),
+                Color::Black => -eval::evaluate(&leaf),
+            };
+            let linear = dataset.evaluate(&entry, &weights);
+            assert!(
+                (linear - expected as f64).abs() <= 1.0,
+                "{fen}: {linear} {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_skips_mated_positions() {
+        // a mated position has no quiet leaf to fit
+        let game = GameState::try_from_fen("R5k1/5ppp/8/8/8/8/8/6K1 b - - 1 1").unwrap();
+        let mut dataset = Dataset::default();
+        dataset.push(game, Label::Result(1.0), &unit_params());
+        assert!(dataset.entries.is_empty());
+    }
+
+    #[test]
+    fn test_coefficient_arithmetic() {
+        let a = Coefficients(vec![(1, 2), (4, 1)]);
+        let b = Coefficients(vec![(0, 3), (4, 1), (7, -2)]);
+        assert_eq!(
+            a.clone() + b.clone(),
+            Coefficients(vec![(0, 3), (1, 2), (4, 2), (7, -2)])
+        );
+        assert_eq!(a.clone() - a.clone(), Coefficients::default());
+        assert_eq!(b * 2, Coefficients(vec![(0, 6), (4, 2), (7, -4)]));
+    }
+
+    #[test]
+    fn test_writes_current_params() {
+        assert_eq!(
+            write_params(&DEFAULT_PARAMS),
+            include_str!("../eval/params.rs")
+        );
+        let weights: Vec<[f64; 2]> = DEFAULT_PARAMS
+            .to_vec()
+            .into_iter()
+            .map(|score| [score.mg as f64 + 0.2, score.eg as f64 - 0.2])
+            .collect();
+        assert_eq!(to_params(&weights), DEFAULT_PARAMS);
+    }
+
+    #[test]
+    fn test_tuning_reduces_error() {
+        // white is a queen up in every won game, material should grow
+        let lines = [
+            "4k3/8/8/8/8/8/8/3QK3 w - - 1-0",
+            "4k3/8/8/8/8/8/8/3QK3 b - - 1-0",
+            "3qk3/8/8/8/8/8/8/4K3 w - - 0-1",
+            "4k3/8/8/8/8/8/8/4K3 w - - 1/2-1/2",
+        ];
+        let dataset = load(&lines, 2, |line| parse_line(line));
+        assert_eq!(dataset.entries.len(), 4);
+        let cli = Cli {
+            dataset: PathBuf::new(),
+            output: std::env::temp_dir().join("rust-chess-tune-test.rs"),
+            threads: 2,
+            epochs: 50,
+            learning_rate: 5.0,
+            k: Some(1.0),
+            limit: None,
+            packed: false,
+            scores: false,
+        };
+        let weights = |params: &EvalParams| -> Vec<[f64; 2]> {
+            params
+                .to_vec()
+                .into_iter()
+                .map(|score| [score.mg as f64, score.eg as f64])
+                .collect()
+        };
+        let before = mean_error(&dataset, &weights(&DEFAULT_PARAMS), 1.0, 2);
+        let params = tune(&cli, &dataset);
+        let after = mean_error(&dataset, &weights(&params), 1.0, 2);
+        assert!(after < before, "{before} {after}");
+        assert!(params.material[4].eg > DEFAULT_PARAMS.material[4].eg);
+        let _ = fs::remove_file(&cli.output);
+    }
+}
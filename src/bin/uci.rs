@@ -0,0 +1,749 @@
+use rust_chess::{
+    eval,
+    nnue::Network,
+    search::{
+        is_mate_score, search_threads, Clock, SearchInfo, SearchLimits, SearchOptions,
+        SearchResult, Skill, TranspositionTable, DEFAULT_HASH_MB, DEFAULT_MOVE_OVERHEAD,
+        MATE_SCORE, MAX_ELO, MAX_SKILL_LEVEL, MIN_ELO,
+    },
+    Color, GameState,
+};
+use std::{
+    io::{self, BufRead},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+#[cfg(feature = "jemalloc")]
+#[global_allocator]
+static GLOBAL: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
+
+const ENGINE_NAME: &str = "rust-chess";
+const ENGINE_AUTHOR: &str = "escobar-west";
+const MAX_HASH_MB: usize = 65536;
+const MAX_MOVE_OVERHEAD_MS: u64 = 5000;
+const MAX_THREADS: usize = 256;
+const MAX_MULTIPV: usize = 256;
+
+const GO_KEYWORDS: [&str; 14] = [
+    "searchmoves",
+    "ponder",
+    "wtime",
+    "btime",
+    "winc",
+    "binc",
+    "movestogo",
+    "depth",
+    "nodes",
+    "mate",
+    "movetime",
+    "infinite",
+    "perft",
+    "go",
+];
+
+type Toggle = fn(&mut SearchOptions) -> &mut bool;
+
+static SEARCH_TOGGLES: [(&str, Toggle); 8] = [
+    ("NullMove", |o| &mut o.null_move),
+    ("LateMoveReductions", |o| &mut o.late_move_reductions),
+    ("ReverseFutility", |o| &mut o.reverse_futility),
+    ("Futility", |o| &mut o.futility),
+    ("Razoring", |o| &mut o.razoring),
+    ("CheckExtensions", |o| &mut o.check_extensions),
+    ("SingularExtensions", |o| &mut o.singular_extensions),
+    ("AspirationWindows", |o| &mut o.aspiration_windows),
+];
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct GoParams {
+    depth: Option<u32>,
+    nodes: Option<u64>,
+    movetime: Option<u64>,
+    wtime: Option<u64>,
+    btime: Option<u64>,
+    winc: Option<u64>,
+    binc: Option<u64>,
+    movestogo: Option<u32>,
+    infinite: bool,
+    ponder: bool,
+    perft: Option<u32>,
+    mate: Option<u32>,
+    searchmoves: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Command {
+    Uci,
+    IsReady,
+    UciNewGame,
+    Position {
+        fen: Option<String>,
+        moves: Vec<String>,
+    },
+    Go(GoParams),
+    Stop,
+    PonderHit,
+    SetOption {
+        name: String,
+        value: Option<String>,
+    },
+    Display,
+    Eval,
+    Quit,
+}
+
+fn parse_command(line: &str) -> Option<Command> {
+    let mut tokens = line.split_whitespace();
+    let command = match tokens.next()? {
+        "uci" => Command::Uci,
+        "isready" => Command::IsReady,
+        "ucinewgame" => Command::UciNewGame,
+        "position" => parse_position(tokens)?,
+        "go" => Command::Go(parse_go(tokens)),
+        "stop" => Command::Stop,
+        "ponderhit" => Command::PonderHit,
+        "setoption" => parse_setoption(tokens)?,
+        "d" => Command::Display,
+        "eval" => Command::Eval,
+        "quit" => Command::Quit,
+        _ => return None,
+    };
+    Some(command)
+}
+
+fn parse_position<'a>(mut tokens: impl Iterator<Item = &'a str>) -> Option<Command> {
+    let fen = match tokens.next()? {
+        "startpos" => {
+            if tokens.next().is_some_and(|t| t != "moves") {
+                return None;
+            }
+            None
+        }
+        "fen" => Some(
+            tokens
+                .by_ref()
+                .take_while(|&t| t != "moves")
+                .collect::<Vec<_>>()
+                .join(" "),
+        ),
+        _ => return None,
+    };
+    let moves = tokens.map(String::from).collect();
+    Some(Command::Position { fen, moves })
+}
+
+fn parse_go<'a>(tokens: impl Iterator<Item = &'a str>) -> GoParams {
+    let mut tokens = tokens.peekable();
+    let mut params = GoParams::default();
+    while let Some(token) = tokens.next() {
+        match token {
+            "infinite" => params.infinite = true,
+            "ponder" => params.ponder = true,
+            "depth" => params.depth = tokens.next().and_then(|t| t.parse().ok()),
+            "nodes" => params.nodes = tokens.next().and_then(|t| t.parse().ok()),
+            "movetime" => params.movetime = tokens.next().and_then(|t| t.parse().ok()),
+            "wtime" => params.wtime = tokens.next().and_then(|t| t.parse().ok()),
+            "btime" => params.btime = tokens.next().and_then(|t| t.parse().ok()),
+            "winc" => params.winc = tokens.next().and_then(|t| t.parse().ok()),
+            "binc" => params.binc = tokens.next().and_then(|t| t.parse().ok()),
+            "movestogo" => params.movestogo = tokens.next().and_then(|t| t.parse().ok()),
+            "perft" => params.perft = tokens.next().and_then(|t| t.parse().ok()),
+            "mate" => params.mate = tokens.next().and_then(|t| t.parse().ok()),
+            // every move up to the next keyword
+            "searchmoves" => {
+                while let Some(uci) = tokens.next_if(|t| !GO_KEYWORDS.contains(t)) {
+                    params.searchmoves.push(uci.to_string());
+                }
+            }
+            _ => (),
+        }
+    }
+    params
+}
+
+fn parse_setoption<'a>(tokens: impl Iterator<Item = &'a str>) -> Option<Command> {
+    let mut name = Vec::new();
+    let mut value = Vec::new();
+    let mut in_value = false;
+    for token in tokens {
+        match token {
+            "name" if !in_value && name.is_empty() => (),
+            "value" if !in_value => in_value = true,
+            _ if in_value => value.push(token),
+            _ => name.push(token),
+        }
+    }
+    if name.is_empty() {
+        return None;
+    }
+    Some(Command::SetOption {
+        name: name.join(" "),
+        value: in_value.then(|| value.join(" ")),
+    })
+}
+
+fn to_limits(params: &GoParams, turn: Color, move_overhead: Duration) -> SearchLimits {
+    let (time, increment) = match turn {
+        Color::White => (params.wtime, params.winc),
+        Color::Black => (params.btime, params.binc),
+    };
+    SearchLimits {
+        depth: params.depth,
+        nodes: params.nodes,
+        movetime: params.movetime.map(Duration::from_millis),
+        clock: time.map(|time| Clock {
+            time: Duration::from_millis(time),
+            increment: Duration::from_millis(increment.unwrap_or(0)),
+            moves_to_go: params.movestogo,
+        }),
+        move_overhead,
+        mate: params.mate,
+        searchmoves: Vec::new(),
+        multipv: 1,
+        skill: None,
+        ponder: params.ponder.then(|| Arc::new(AtomicBool::new(true))),
+        infinite: params.infinite,
+    }
+}
+
+fn format_score(score: i32) -> String {
+    if is_mate_score(score) {
+        let plies = MATE_SCORE - score.abs();
+        let moves = (plies + 1) / 2;
+        match score > 0 {
+            true => format!("mate {moves}"),
+            false => format!("mate -{moves}"),
+        }
+    } else {
+        format!("cp {score}")
+    }
+}
+
+fn format_info(info: &SearchInfo) -> String {
+    let pv: Vec<String> = info.pv.iter().map(|m| m.to_string()).collect();
+    format!(
+        "info depth {} seldepth {} multipv {} score {} nodes {} nps {} hashfull {} time {} pv {}",
+        info.depth,
+        info.seldepth,
+        info.multipv,
+        format_score(info.score),
+        info.nodes,
+        info.nps,
+        info.hashfull,
+        info.time.as_millis(),
+        pv.join(" ")
+    )
+}
+
+// the expected reply is what the gui will ponder on
+fn format_bestmove(result: &SearchResult) -> String {
+    match (result.best_move, result.pv.as_slice()) {
+        (Some(move_), [first, reply, ..]) if *first == move_ => {
+            format!("bestmove {move_} ponder {reply}")
+        }
+        (Some(move_), _) => format!("bestmove {move_}"),
+        (None, _) => String::from("bestmove 0000"),
+    }
+}
+
+struct SearchThread {
+    handle: JoinHandle<()>,
+    stop: Arc<AtomicBool>,
+    ponder: Option<Arc<AtomicBool>>,
+}
+
+struct Engine {
+    game: GameState,
+    options: SearchOptions,
+    move_overhead: Duration,
+    threads: usize,
+    multipv: usize,
+    skill_level: u32,
+    // UCI_Elo replaces the skill level while strength is limited
+    limit_strength: bool,
+    elo: u32,
+    tt: Arc<TranspositionTable>,
+    network: Option<Arc<Network>>,
+    search: Option<SearchThread>,
+}
+
+impl Engine {
+    fn new() -> Self {
+        Self {
+            game: GameState::default(),
+            options: SearchOptions::default(),
+            move_overhead: DEFAULT_MOVE_OVERHEAD,
+            threads: 1,
+            multipv: 1,
+            skill_level: MAX_SKILL_LEVEL,
+            limit_strength: false,
+            elo: MIN_ELO,
+            tt: Arc::new(TranspositionTable::new(DEFAULT_HASH_MB)),
+            network: None,
+            search: None,
+        }
+    }
+
+    // returns false once the engine should exit
+    fn handle(&mut self, command: Command) -> bool {
+        match command {
+            Command::Uci => {
+                println!("id name {ENGINE_NAME} {}", env!("CARGO_PKG_VERSION"));
+                println!("id author {ENGINE_AUTHOR}");
+                println!(
+                    "option name Hash type spin default {DEFAULT_HASH_MB} min 1 max {MAX_HASH_MB}"
+                );
+                println!("option name Clear Hash type button");
+                println!("option name Threads type spin default 1 min 1 max {MAX_THREADS}");
+                println!("option name Ponder type check default false");
+                println!("option name MultiPV type spin default 1 min 1 max {MAX_MULTIPV}");
+                println!(
+                    "option name Skill Level type spin default {MAX_SKILL_LEVEL} min 0 max {MAX_SKILL_LEVEL}"
+                );
+                println!("option name UCI_LimitStrength type check default false");
+                println!(
+                    "option name UCI_Elo type spin default {MIN_ELO} min {MIN_ELO} max {MAX_ELO}"
+                );
+                println!(
+                    "option name Move Overhead type spin default {} min 0 max {MAX_MOVE_OVERHEAD_MS}",
+                    DEFAULT_MOVE_OVERHEAD.as_millis()
+                );
+                println!("option name EvalFile type string default <empty>");
+                for (name, toggle) in &SEARCH_TOGGLES {
+                    let default = *toggle(&mut SearchOptions::default());
+                    println!("option name {name} type check default {default}");
+                }
+                println!("uciok");
+            }
+            Command::IsReady => println!("readyok"),
+            Command::UciNewGame => {
+                self.stop_search();
+                self.game = GameState::default();
+                self.game.set_network(self.network.clone());
+                self.tt_mut().clear();
+            }
+            Command::Position { fen, moves } => {
+                self.stop_search();
+                if let Err(err) = self.set_position(fen.as_deref(), &moves) {
+                    println!("info string {err}");
+                }
+            }
+            Command::Go(params) => {
+                self.stop_search();
+                match params.perft {
+                    Some(depth) => self.perft(depth),
+                    None => self.start_search(&params),
+                }
+            }
+            Command::Stop => self.stop_search(),
+            Command::PonderHit => self.ponderhit(),
+            Command::SetOption { name, value } => {
+                self.stop_search();
+                if let Err(err) = self.set_option(&name, value.as_deref()) {
+                    println!("info string {err}");
+                }
+            }
+            Command::Display => {
+                self.game.board().print_board();
+                println!("Fen: {}", self.game.to_fen());
+            }
+            Command::Eval => {
+                println!("{}", eval::trace(&self.game));
+                if self.network.is_some() {
+                    println!(
+                        "NNUE evaluation: {} cp (side to move)",
+                        eval::evaluate(&self.game)
+                    );
+                }
+            }
+            Command::Quit => {
+                self.stop_search();
+                return false;
+            }
+        }
+        true
+    }
+
+    fn set_position(&mut self, fen: Option<&str>, moves: &[String]) -> Result<(), &'static str> {
+        let mut game = match fen {
+            Some(fen) => GameState::try_from_fen(fen)?,
+            None => GameState::default(),
+        };
+        game.set_network(self.network.clone());
+        for uci in moves {
+            let move_ = game.parse_move(uci)?;
+            game.make_move(move_);
+        }
+        self.game = game;
+        Ok(())
+    }
+
+    // unknown options are ignored
+    fn set_option(&mut self, name: &str, value: Option<&str>) -> Result<(), &'static str> {
+        match name.to_ascii_lowercase().as_str() {
+            "hash" => {
+                let megabytes = value
+                    .and_then(|v| v.parse().ok())
+                    .filter(|mb| (1..=MAX_HASH_MB).contains(mb))
+                    .ok_or("Invalid Hash value")?;
+                self.tt_mut().resize(megabytes);
+            }
+            "clear hash" => self.tt_mut().clear(),
+            "threads" => {
+                self.threads = value
+                    .and_then(|v| v.parse().ok())
+                    .filter(|n| (1..=MAX_THREADS).contains(n))
+                    .ok_or("Invalid Threads value")?;
+            }
+            // only tells the gui that it may send go ponder
+            "ponder" => (),
+            "multipv" => {
+                self.multipv = value
+                    .and_then(|v| v.parse().ok())
+                    .filter(|n| (1..=MAX_MULTIPV).contains(n))
+                    .ok_or("Invalid MultiPV value")?;
+            }
+            "skill level" => {
+                self.skill_level = value
+                    .and_then(|v| v.parse().ok())
+                    .filter(|level| *level <= MAX_SKILL_LEVEL)
+                    .ok_or("Invalid Skill Level value")?;
+            }
+            "uci_limitstrength" => {
+                self.limit_strength = match value {
+                    Some("true") => true,
+                    Some("false") => false,
+                    _ => return Err("Invalid check value"),
+                };
+            }
+            "uci_elo" => {
+                self.elo = value
+                    .and_then(|v| v.parse().ok())
+                    .filter(|elo| (MIN_ELO..=MAX_ELO).contains(elo))
+                    .ok_or("Invalid UCI_Elo value")?;
+            }
+            "move overhead" => {
+                let millis = value
+                    .and_then(|v| v.parse().ok())
+                    .filter(|ms| *ms <= MAX_MOVE_OVERHEAD_MS)
+                    .ok_or("Invalid Move Overhead value")?;
+                self.move_overhead = Duration::from_millis(millis);
+            }
+            // the hand-crafted evaluation is used without a network
+            "evalfile" => {
+                self.network = match value {
+                    None | Some("<empty>") => None,
+                    Some(path) => Some(Arc::new(Network::load(path)?)),
+                };
+                self.game.set_network(self.network.clone());
+            }
+            name => {
+                let toggle = SEARCH_TOGGLES
+                    .iter()
+                    .find(|(option, _)| option.eq_ignore_ascii_case(name));
+                if let Some((_, toggle)) = toggle {
+                    *toggle(&mut self.options) = match value {
+                        Some("true") => true,
+                        Some("false") => false,
+                        _ => return Err("Invalid check value"),
+                    };
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // a new seed for every search so the same position isn't always answered
+    // with the same weak move
+    fn get_skill(&self) -> Option<Skill> {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |t| t.as_nanos() as u64);
+        let skill = match self.limit_strength {
+            true => Skill::from_elo(self.elo, seed),
+            false => Skill::new(self.skill_level as f64, seed),
+        };
+        skill.is_enabled().then_some(skill)
+    }
+
+    // only valid while no search thread holds the table
+    fn tt_mut(&mut self) -> &mut TranspositionTable {
+        Arc::get_mut(&mut self.tt).expect("transposition table is shared with a search")
+    }
+
+    fn start_search(&mut self, params: &GoParams) {
+        let mut limits = to_limits(params, self.game.turn(), self.move_overhead);
+        limits.multipv = self.multipv;
+        limits.skill = self.get_skill();
+        limits.searchmoves = params
+            .searchmoves
+            .iter()
+            .filter_map(|uci| self.game.parse_move(uci).ok())
+            .collect();
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = Arc::clone(&stop);
+        let tt = Arc::clone(&self.tt);
+        let ponder = limits.ponder.clone();
+        let (options, threads) = (self.options, self.threads);
+        let mut game = self.game.clone();
+        let handle = thread::spawn(move || {
+            let on_info = |info: &SearchInfo| println!("{}", format_info(info));
+            let result = search_threads(
+                &mut game,
+                &tt,
+                &limits,
+                &options,
+                threads,
+                &thread_stop,
+                on_info,
+            );
+            // bestmove must not be sent before stop when searching infinitely,
+            // or before stop or ponderhit when pondering
+            let pondering = |limits: &SearchLimits| {
+                let ponder = limits.ponder.as_ref();
+                ponder.is_some_and(|ponder| ponder.load(Ordering::Relaxed))
+            };
+            while (limits.infinite || pondering(&limits)) && !thread_stop.load(Ordering::Relaxed) {
+                thread::sleep(Duration::from_millis(1));
+            }
+            println!("{}", format_bestmove(&result));
+        });
+        self.search = Some(SearchThread {
+            handle,
+            stop,
+            ponder,
+        });
+    }
+
+    fn stop_search(&mut self) {
+        if let Some(SearchThread { handle, stop, .. }) = self.search.take() {
+            stop.store(true, Ordering::Relaxed);
+            handle.join().unwrap();
+        }
+    }
+
+    // the expected move was played, the search goes on with the clock running
+    fn ponderhit(&mut self) {
+        let ponder = self
+            .search
+            .as_ref()
+            .and_then(|search| search.ponder.as_ref());
+        if let Some(ponder) = ponder {
+            ponder.store(false, Ordering::Relaxed);
+        }
+    }
+
+    fn perft(&mut self, depth: u32) {
+        let mut total = 0;
+        for move_ in self.game.legal_moves() {
+            self.game.make_move(move_);
+            let nodes = match depth {
+                0 | 1 => 1,
+                _ => self.tt.perft(&mut self.game, depth - 1),
+            };
+            self.game.pop_move();
+            println!("{move_}: {nodes}");
+            total += nodes;
+        }
+        println!();
+        println!("Nodes searched: {total}");
+    }
+}
+
+fn main() {
+    let mut engine = Engine::new();
+    for line in io::stdin().lock().lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        let Some(command) = parse_command(&line) else {
+            continue;
+        };
+        if !engine.handle(command) {
+            return;
+        }
+    }
+    engine.stop_search();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_position() {
+        assert_eq!(
+            parse_command("position startpos moves e2e4 e7e5"),
+            Some(Command::Position {
+                fen: None,
+                moves: vec![String::from("e2e4"), String::from("e7e5")],
+            })
+        );
+        let fen = "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1";
+        assert_eq!(
+            parse_command(&format!("position fen {fen}")),
+            Some(Command::Position {
+                fen: Some(String::from(fen)),
+                moves: vec![],
+            })
+        );
+        assert_eq!(parse_command("position"), None);
+    }
+
+    #[test]
+    fn test_bad_position_fen() {
+        let mut engine = Engine::new();
+        let fen = "rnbqkbnrr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        let command = parse_command(&format!("position fen {fen}")).unwrap();
+        // the error is reported and the engine keeps its position
+        assert!(engine.handle(command));
+        assert_eq!(engine.game.to_fen().as_ref(), rust_chess::DEFAULT_FEN);
+        assert!(engine.set_position(Some(fen), &[]).is_err());
+        assert!(engine
+            .set_position(Some("9/8/8/8/8/8/8/8 w - - 0 1"), &[])
+            .is_err());
+    }
+
+    #[test]
+    fn test_parse_simple_commands() {
+        assert_eq!(parse_command("eval"), Some(Command::Eval));
+        assert_eq!(parse_command("d"), Some(Command::Display));
+        assert_eq!(parse_command("  isready  "), Some(Command::IsReady));
+        assert_eq!(parse_command("evaluate"), None);
+    }
+
+    #[test]
+    fn test_parse_go() {
+        let Some(Command::Go(params)) =
+            parse_command("go wtime 60000 btime 50000 winc 1000 binc 500 movestogo 20")
+        else {
+            panic!("expected go command");
+        };
+        assert_eq!(params.wtime, Some(60000));
+        assert_eq!(params.binc, Some(500));
+        assert_eq!(params.movestogo, Some(20));
+        let overhead = Duration::from_millis(30);
+        let limits = to_limits(&params, Color::White, overhead);
+        assert_eq!(
+            limits.clock,
+            Some(Clock {
+                time: Duration::from_millis(60000),
+                increment: Duration::from_millis(1000),
+                moves_to_go: Some(20),
+            })
+        );
+        assert_eq!(limits.move_overhead, overhead);
+        let limits = to_limits(&params, Color::Black, overhead);
+        assert_eq!(limits.clock.unwrap().time, Duration::from_millis(50000));
+        assert_eq!(limits.movetime, None);
+
+        let Some(Command::Go(params)) = parse_command("go perft 3") else {
+            panic!("expected go command");
+        };
+        assert_eq!(params.perft, Some(3));
+        let Some(Command::Go(params)) = parse_command("go ponder wtime 1000") else {
+            panic!("expected go command");
+        };
+        let limits = to_limits(&params, Color::White, overhead);
+        assert!(!limits.infinite);
+        assert!(limits.ponder.unwrap().load(Ordering::Relaxed));
+        assert!(limits.clock.is_some());
+        let Some(Command::Go(params)) = parse_command("go searchmoves e2e4 d2d4 mate 3") else {
+            panic!("expected go command");
+        };
+        assert_eq!(params.searchmoves, ["e2e4", "d2d4"]);
+        assert_eq!(to_limits(&params, Color::White, overhead).mate, Some(3));
+    }
+
+    #[test]
+    fn test_parse_setoption() {
+        assert_eq!(
+            parse_command("setoption name Clear Hash"),
+            Some(Command::SetOption {
+                name: String::from("Clear Hash"),
+                value: None,
+            })
+        );
+        assert_eq!(
+            parse_command("setoption name Hash value 64"),
+            Some(Command::SetOption {
+                name: String::from("Hash"),
+                value: Some(String::from("64")),
+            })
+        );
+    }
+
+    #[test]
+    fn test_format_score() {
+        assert_eq!(format_score(35), "cp 35");
+        assert_eq!(format_score(MATE_SCORE - 1), "mate 1");
+        assert_eq!(format_score(MATE_SCORE - 3), "mate 2");
+        assert_eq!(format_score(-MATE_SCORE + 2), "mate -1");
+    }
+
+    #[test]
+    fn test_format_bestmove() {
+        let mut game = GameState::default();
+        let first = game.parse_move("e2e4").unwrap();
+        game.make_move(first);
+        let pv = vec![first, game.parse_move("e7e5").unwrap()];
+        let mut result = SearchResult {
+            best_move: Some(pv[0]),
+            score: 0,
+            depth: 2,
+            nodes: 0,
+            pv,
+            lines: Vec::new(),
+        };
+        assert_eq!(format_bestmove(&result), "bestmove e2e4 ponder e7e5");
+        result.pv.truncate(1);
+        assert_eq!(format_bestmove(&result), "bestmove e2e4");
+        result.best_move = None;
+        assert_eq!(format_bestmove(&result), "bestmove 0000");
+    }
+
+    #[test]
+    fn test_set_option() {
+        let mut engine = Engine::new();
+        assert!(engine.set_option("Hash", Some("1")).is_ok());
+        assert!(engine.set_option("hash", Some("0")).is_err());
+        assert!(engine.set_option("Hash", None).is_err());
+        assert!(engine.set_option("Clear Hash", None).is_ok());
+        assert!(engine.set_option("Unknown", Some("1")).is_ok());
+        assert!(engine.set_option("NullMove", Some("false")).is_ok());
+        assert!(!engine.options.null_move);
+        assert!(engine.set_option("razoring", Some("maybe")).is_err());
+        assert!(engine.options.razoring);
+        assert!(engine.set_option("MultiPV", Some("3")).is_ok());
+        assert_eq!(engine.multipv, 3);
+        assert!(engine.set_option("MultiPV", Some("0")).is_err());
+        assert!(engine.get_skill().is_none());
+        assert!(engine.set_option("Skill Level", Some("3")).is_ok());
+        assert_eq!(engine.get_skill().unwrap().get_level(), 3.0);
+        assert!(engine.set_option("Skill Level", Some("21")).is_err());
+        assert!(engine.set_option("UCI_LimitStrength", Some("true")).is_ok());
+        assert!(engine.set_option("UCI_Elo", Some("100")).is_err());
+        assert!(engine
+            .set_option("UCI_Elo", Some(&MAX_ELO.to_string()))
+            .is_ok());
+        assert!(engine.get_skill().is_none());
+    }
+
+    #[test]
+    fn test_set_position() {
+        let mut engine = Engine::new();
+        engine
+            .set_position(None, &[String::from("e2e4"), String::from("e7e5")])
+            .unwrap();
+        assert_eq!(
+            engine.game.to_fen().as_ref(),
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2"
+        );
+        assert!(engine.set_position(None, &[String::from("e2e5")]).is_err());
+    }
+}
@@ -1,19 +1,27 @@
 mod bitboard;
 mod components;
 mod mailbox;
+pub mod squares;
 
-use crate::pieces::{constants::*, Color, Figure, Piece};
+use crate::{
+    eval::{self, Score},
+    nnue::{Accumulator, Network},
+    pieces::{constants::*, Color, Figure, Piece},
+    zobrist,
+};
+use alloc::{boxed::Box, format, string::String, sync::Arc, vec::Vec};
 pub use bitboard::{BitBoard, EMPTY_BOARD, FULL_BOARD};
 use bitboard::{
     Direction, BLACK_PAWN_ATTACKS, DIAG_RAYS, DIAG_SEGMENTS, KING_MOVES, KNIGHT_MOVES, NOT_H_FILE,
     STRAIGHT_RAYS, STRAIGHT_SEGMENTS, WHITE_PAWN_ATTACKS,
 };
 pub use components::{Column, Row, Square};
+use core::{fmt, str::FromStr};
 use mailbox::MailBox;
 
 type BitBoardRayTable = [[BitBoard; 4]; 64];
 
-#[derive(Debug, Default, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
 struct PieceSet {
     pawns: BitBoard,
     rooks: BitBoard,
@@ -23,7 +31,7 @@ struct PieceSet {
     kings: BitBoard,
 }
 
-#[derive(Debug, Default, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct Board {
     white_pieces: PieceSet,
     black_pieces: PieceSet,
@@ -31,6 +39,12 @@ pub struct Board {
     black_occupied: BitBoard,
     occupied: BitBoard,
     mailbox: MailBox,
+    hash: u64,
+    // kept up to date for the evaluation
+    piece_counts: [[u8; 6]; 2],
+    pst: [Score; 2],
+    phase: i32,
+    nnue: Option<Accumulator>,
 }
 
 impl Board {
@@ -66,10 +80,38 @@ impl Board {
         self.mailbox.get_square(square)
     }
 
+    pub fn get_hash(&self) -> u64 {
+        self.hash
+    }
+
+    pub fn get_piece_count(&self, piece: Piece) -> u32 {
+        self.piece_counts[piece.color as usize][piece.figure as usize] as u32
+    }
+
+    // sum of the default piece-square values of one side
+    pub fn get_pst(&self, color: Color) -> Score {
+        self.pst[color as usize]
+    }
+
+    // not capped, promotions can take it past eval::MAX_PHASE
+    pub fn get_phase(&self) -> i32 {
+        self.phase
+    }
+
+    pub fn get_accumulator(&self) -> Option<&Accumulator> {
+        self.nnue.as_ref()
+    }
+
+    pub fn set_network(&mut self, network: Option<Arc<Network>>) {
+        self.nnue = network.map(|network| Accumulator::new(network, self));
+    }
+
     pub fn clear_square(&mut self, square: Square) -> Option<Piece> {
         let piece = self.mailbox.clear_square(square);
         if let Some(p) = piece {
-            self.clear_bitboards(square.into(), p)
+            self.clear_bitboards(square.into(), p);
+            self.remove_eval_terms(square, p);
+            self.hash ^= zobrist::piece_key(p, square);
         }
         piece
     }
@@ -78,9 +120,13 @@ impl Board {
         let old_piece = self.mailbox.set_square(square, piece);
         let square_mask: BitBoard = square.into();
         if let Some(old_piece) = old_piece {
-            self.clear_bitboards(square_mask, old_piece)
+            self.clear_bitboards(square_mask, old_piece);
+            self.remove_eval_terms(square, old_piece);
+            self.hash ^= zobrist::piece_key(old_piece, square);
         }
         self.set_bitboards(square_mask, piece);
+        self.add_eval_terms(square, piece);
+        self.hash ^= zobrist::piece_key(piece, square);
         old_piece
     }
 
@@ -179,31 +225,50 @@ impl Board {
     }
 
     pub fn is_attacked_by(&self, target_sq: Square, attack_color: Color) -> bool {
+        (self.attackers_to(target_sq, self.occupied) & self.get_color(attack_color)).is_not_empty()
+    }
+
+    pub fn attackers_to(&self, target_sq: Square, occupancy: BitBoard) -> BitBoard {
+        let straight_pieces = self.white_pieces.rooks
+            | self.white_pieces.queens
+            | self.black_pieces.rooks
+            | self.black_pieces.queens;
+        let diag_pieces = self.white_pieces.bishops
+            | self.white_pieces.queens
+            | self.black_pieces.bishops
+            | self.black_pieces.queens;
+        let knights = self.white_pieces.knights | self.black_pieces.knights;
+        let kings = self.white_pieces.kings | self.black_pieces.kings;
+        let attackers = (get_blocked_rays(target_sq, occupancy, &STRAIGHT_RAYS) & straight_pieces)
+            | (get_blocked_rays(target_sq, occupancy, &DIAG_RAYS) & diag_pieces)
+            | (target_sq.get_knight_moves() & knights)
+            | (target_sq.get_king_moves() & kings)
+            | (self.get_pawn_attacks(target_sq, Color::Black) & self.white_pieces.pawns)
+            | (self.get_pawn_attacks(target_sq, Color::White) & self.black_pieces.pawns);
+        attackers & occupancy
+    }
+
+    pub fn xray_attackers_to(&self, target_sq: Square, blockers: BitBoard) -> BitBoard {
+        self.attackers_to(target_sq, self.occupied & !blockers)
+    }
+
+    pub fn attacks_by(&self, attack_color: Color) -> BitBoard {
         let attackers = self.get_piece_set(attack_color);
-        let straight_attackers = attackers.rooks | attackers.queens;
-        for attacker_sq in straight_attackers.iter_forward() {
-            let segment = get_straight_segment(attacker_sq, target_sq);
-            if segment & self.occupied == target_sq.as_bitboard() {
-                return true;
-            }
-        }
-        let diag_attackers = attackers.bishops | attackers.queens;
-        for attacker_sq in diag_attackers.iter_forward() {
-            let segment = get_diag_segment(attacker_sq, target_sq);
-            if segment & self.occupied == target_sq.as_bitboard() {
-                return true;
-            }
-        }
-        if (target_sq.get_knight_moves() & attackers.knights).is_not_empty() {
-            return true;
-        }
-        if (self.get_pawn_attacks(target_sq, !attack_color) & attackers.pawns).is_not_empty() {
-            return true;
+        get_all_attacks_mask(attackers, attack_color, self.occupied)
+    }
+
+    pub fn attacks_from(&self, square: Square) -> BitBoard {
+        match self.get_square(square) {
+            Some(piece) => self.get_piece_attacks(square, piece, self.occupied),
+            None => EMPTY_BOARD,
         }
-        if (target_sq.get_king_moves() & attackers.kings).is_not_empty() {
-            return true;
+    }
+
+    pub fn xray_attacks_from(&self, square: Square, blockers: BitBoard) -> BitBoard {
+        match self.get_square(square) {
+            Some(piece) => self.get_piece_attacks(square, piece, self.occupied & !blockers),
+            None => EMPTY_BOARD,
         }
-        false
     }
 
     pub fn try_from_fen(fen: &str) -> Result<Self, &'static str> {
@@ -216,14 +281,26 @@ impl Board {
         for (row_idx, fen_row) in (0..8u8).rev().zip(fen_positions.iter()) {
             let mut col_idx = 0u8;
             for c in fen_row.chars() {
-                if c.is_ascii_digit() {
-                    col_idx += c.to_digit(10).unwrap() as u8;
+                if let Some(empty) = c.to_digit(10) {
+                    if !(1..=8).contains(&empty) {
+                        return Err("Invalid empty square count");
+                    }
+                    col_idx += empty as u8;
                 } else {
                     let piece = Piece::try_from(c)?;
+                    if col_idx >= 8 {
+                        return Err("Too many squares in row");
+                    }
                     let square = Square::from_coords(Row::new(row_idx), Column::new(col_idx));
                     board.set_square(square, piece);
                     col_idx += 1;
                 }
+                if col_idx > 8 {
+                    return Err("Too many squares in row");
+                }
+            }
+            if col_idx != 8 {
+                return Err("Too few squares in row");
             }
         }
         Ok(board)
@@ -257,6 +334,7 @@ impl Board {
         fen_row_list.join("/").into_boxed_str()
     }
 
+    #[cfg(feature = "std")]
     pub fn print_board(&self) {
         let mut char_board: [char; 64] = ['☐'; 64];
         for piece in [
@@ -333,6 +411,24 @@ impl Board {
         self.occupied |= mask;
     }
 
+    fn add_eval_terms(&mut self, square: Square, piece: Piece) {
+        self.piece_counts[piece.color as usize][piece.figure as usize] += 1;
+        self.pst[piece.color as usize] += eval::get_piece_square(piece, square);
+        self.phase += eval::get_phase_weight(piece.figure);
+        if let Some(nnue) = &mut self.nnue {
+            nnue.add(piece, square);
+        }
+    }
+
+    fn remove_eval_terms(&mut self, square: Square, piece: Piece) {
+        self.piece_counts[piece.color as usize][piece.figure as usize] -= 1;
+        self.pst[piece.color as usize] -= eval::get_piece_square(piece, square);
+        self.phase -= eval::get_phase_weight(piece.figure);
+        if let Some(nnue) = &mut self.nnue {
+            nnue.remove(piece, square);
+        }
+    }
+
     fn get_pawn_attacks(&self, square: Square, color: Color) -> BitBoard {
         match color {
             Color::White => WHITE_PAWN_ATTACKS[usize::from(square)],
@@ -340,6 +436,20 @@ impl Board {
         }
     }
 
+    fn get_piece_attacks(&self, square: Square, piece: Piece, occupancy: BitBoard) -> BitBoard {
+        match piece.figure {
+            Figure::Pawn => self.get_pawn_attacks(square, piece.color),
+            Figure::Rook => get_blocked_rays(square, occupancy, &STRAIGHT_RAYS),
+            Figure::Knight => square.get_knight_moves(),
+            Figure::Bishop => get_blocked_rays(square, occupancy, &DIAG_RAYS),
+            Figure::Queen => {
+                get_blocked_rays(square, occupancy, &STRAIGHT_RAYS)
+                    | get_blocked_rays(square, occupancy, &DIAG_RAYS)
+            }
+            Figure::King => square.get_king_moves(),
+        }
+    }
+
     fn get_pawn_moves(&self, square: Square, color: Color) -> BitBoard {
         match color {
             Color::White => {
@@ -356,6 +466,19 @@ impl Board {
     }
 }
 
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_fen())
+    }
+}
+
+impl FromStr for Board {
+    type Err = &'static str;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::try_from_fen(s)
+    }
+}
+
 fn get_straight_segment(from: Square, to: Square) -> BitBoard {
     STRAIGHT_SEGMENTS[from.as_usize()][to.as_usize()]
 }
@@ -2,8 +2,8 @@ mod constants;
 mod iters;
 use super::Square;
 pub use constants::*;
+use core::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not, Shl, Shr};
 use iters::BitBoardFwdIter;
-use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not, Shl, Shr};
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[repr(usize)]
@@ -30,11 +30,13 @@ impl BitBoard {
         self.0 != 0
     }
 
+    pub const fn pop_count(self) -> u32 {
+        self.0.count_ones()
+    }
+
     fn pop_lsb(&mut self) -> Option<Square> {
-        self.bitscan_forward().map(|lsb| {
-            *self ^= BitBoard::from(lsb);
-            lsb
-        })
+        self.bitscan_forward()
+            .inspect(|&lsb| *self ^= BitBoard::from(lsb))
     }
 
     pub const fn bitscan_forward(self) -> Option<Square> {
@@ -67,15 +69,16 @@ impl BitBoard {
         Self(mask)
     }
 
+    #[cfg(feature = "std")]
     pub fn print_board(self, c: char) {
         let mut char_board: [char; 64] = ['☐'; 64];
         for square in self.iter_forward() {
             char_board[usize::from(square)] = c;
         }
-        let mut out_str = String::new();
+        let mut out_str = alloc::string::String::new();
         for i in (0..8).rev() {
             let offset = 8 * i as usize;
-            let row: String = char_board[offset..offset + 8].iter().collect();
+            let row: alloc::string::String = char_board[offset..offset + 8].iter().collect();
             out_str.push_str(&row);
             out_str.push('\n')
         }
@@ -14,9 +14,8 @@ impl BitBoardFwdIter {
 impl Iterator for BitBoardFwdIter {
     type Item = Square;
     fn next(&mut self) -> Option<Self::Item> {
-        self.rem_mask.bitscan_forward().map(|lsb| {
-            self.rem_mask ^= BitBoard::from(lsb);
-            lsb
-        })
+        self.rem_mask
+            .bitscan_forward()
+            .inspect(|&lsb| self.rem_mask ^= BitBoard::from(lsb))
     }
 }
@@ -1,6 +1,7 @@
 use super::bitboard::{BitBoard, COLUMNS, KING_MOVES, KNIGHT_MOVES, ROWS, SQUARES};
-use std::fmt;
-use std::ops::{Add, Sub};
+use alloc::{boxed::Box, string::String};
+use core::ops::{Add, Sub};
+use core::{fmt, str::FromStr};
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct Row(u8);
@@ -16,7 +17,7 @@ impl Row {
     }
 
     pub const fn is_adjacent(self, other: Self) -> bool {
-        self.0 == other.0 - 1 || self.0 == other.0 + 1
+        self.0.abs_diff(other.0) == 1
     }
 
     pub const fn as_bitboard(self) -> BitBoard {
@@ -58,7 +59,7 @@ impl Column {
     }
 
     pub const fn is_adjacent(self, other: Self) -> bool {
-        self.0 == other.0 - 1 || self.0 == other.0 + 1
+        self.0.abs_diff(other.0) == 1
     }
 
     pub const fn as_bitboard(self) -> BitBoard {
@@ -166,3 +167,23 @@ impl fmt::Display for Square {
         write!(f, "{}", self.to_alg())
     }
 }
+
+impl FromStr for Square {
+    type Err = &'static str;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() != 2 {
+            return Err("invalid square");
+        }
+        Self::try_from_alg(s)
+    }
+}
+
+impl TryFrom<u8> for Square {
+    type Error = &'static str;
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0..64 => Ok(Self(value)),
+            _ => Err("invalid square"),
+        }
+    }
+}
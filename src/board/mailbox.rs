@@ -1,8 +1,8 @@
 use super::Square;
 use crate::pieces::Piece;
 
-#[derive(Debug, PartialEq, Eq)]
-pub struct MailBox(Box<[Option<Piece>; 64]>);
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MailBox([Option<Piece>; 64]);
 
 impl MailBox {
     pub fn get_square(&self, square: Square) -> Option<Piece> {
@@ -20,6 +20,6 @@ impl MailBox {
 
 impl Default for MailBox {
     fn default() -> Self {
-        MailBox(Box::new([None; 64]))
+        MailBox([None; 64])
     }
 }
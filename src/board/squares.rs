@@ -0,0 +1,73 @@
+use super::Square;
+
+pub const A1: Square = Square::new(0);
+pub const B1: Square = Square::new(1);
+pub const C1: Square = Square::new(2);
+pub const D1: Square = Square::new(3);
+pub const E1: Square = Square::new(4);
+pub const F1: Square = Square::new(5);
+pub const G1: Square = Square::new(6);
+pub const H1: Square = Square::new(7);
+
+pub const A2: Square = Square::new(8);
+pub const B2: Square = Square::new(9);
+pub const C2: Square = Square::new(10);
+pub const D2: Square = Square::new(11);
+pub const E2: Square = Square::new(12);
+pub const F2: Square = Square::new(13);
+pub const G2: Square = Square::new(14);
+pub const H2: Square = Square::new(15);
+
+pub const A3: Square = Square::new(16);
+pub const B3: Square = Square::new(17);
+pub const C3: Square = Square::new(18);
+pub const D3: Square = Square::new(19);
+pub const E3: Square = Square::new(20);
+pub const F3: Square = Square::new(21);
+pub const G3: Square = Square::new(22);
+pub const H3: Square = Square::new(23);
+
+pub const A4: Square = Square::new(24);
+pub const B4: Square = Square::new(25);
+pub const C4: Square = Square::new(26);
+pub const D4: Square = Square::new(27);
+pub const E4: Square = Square::new(28);
+pub const F4: Square = Square::new(29);
+pub const G4: Square = Square::new(30);
+pub const H4: Square = Square::new(31);
+
+pub const A5: Square = Square::new(32);
+pub const B5: Square = Square::new(33);
+pub const C5: Square = Square::new(34);
+pub const D5: Square = Square::new(35);
+pub const E5: Square = Square::new(36);
+pub const F5: Square = Square::new(37);
+pub const G5: Square = Square::new(38);
+pub const H5: Square = Square::new(39);
+
+pub const A6: Square = Square::new(40);
+pub const B6: Square = Square::new(41);
+pub const C6: Square = Square::new(42);
+pub const D6: Square = Square::new(43);
+pub const E6: Square = Square::new(44);
+pub const F6: Square = Square::new(45);
+pub const G6: Square = Square::new(46);
+pub const H6: Square = Square::new(47);
+
+pub const A7: Square = Square::new(48);
+pub const B7: Square = Square::new(49);
+pub const C7: Square = Square::new(50);
+pub const D7: Square = Square::new(51);
+pub const E7: Square = Square::new(52);
+pub const F7: Square = Square::new(53);
+pub const G7: Square = Square::new(54);
+pub const H7: Square = Square::new(55);
+
+pub const A8: Square = Square::new(56);
+pub const B8: Square = Square::new(57);
+pub const C8: Square = Square::new(58);
+pub const D8: Square = Square::new(59);
+pub const E8: Square = Square::new(60);
+pub const F8: Square = Square::new(61);
+pub const G8: Square = Square::new(62);
+pub const H8: Square = Square::new(63);
@@ -231,6 +231,78 @@ fn test_straight_pin_south_east() {
     assert_eq!(pin_mask, expected);
 }
 
+#[test]
+fn test_attackers_to() {
+    let fen = "3qk3/8/8/3r4/8/3R4/3Q4/3RK3 w - - 0 1";
+    let board = Board::try_from_fen(fen).unwrap();
+    let target_sq = Square::from_alg("d4");
+    let attackers = board.attackers_to(target_sq, board.get_occupied());
+    assert_eq!(
+        attackers,
+        BitBoard::from_alg("d3") | BitBoard::from_alg("d5")
+    );
+
+    let target_sq = Square::from_alg("e2");
+    let attackers = board.attackers_to(target_sq, board.get_occupied());
+    assert_eq!(
+        attackers,
+        BitBoard::from_alg("d2") | BitBoard::from_alg("e1")
+    );
+    assert!(board.is_attacked_by(target_sq, Color::White));
+    assert!(!board.is_attacked_by(target_sq, Color::Black));
+}
+
+#[test]
+fn test_xray_attackers_to() {
+    let fen = "3qk3/8/8/3r4/8/3R4/3Q4/3RK3 w - - 0 1";
+    let board = Board::try_from_fen(fen).unwrap();
+    let target_sq = Square::from_alg("d4");
+    let blockers = BitBoard::from_alg("d3") | BitBoard::from_alg("d5");
+    let attackers = board.xray_attackers_to(target_sq, blockers);
+    assert_eq!(
+        attackers,
+        BitBoard::from_alg("d2") | BitBoard::from_alg("d8")
+    );
+}
+
+#[test]
+fn test_attacks_from() {
+    let fen = "3qk3/8/8/3r4/8/3R4/3Q4/3RK3 w - - 0 1";
+    let board = Board::try_from_fen(fen).unwrap();
+    let attacks = board.attacks_from(Square::from_alg("d3"));
+    let expected = (BitBoard::from(Row::new(2)) ^ BitBoard::from_alg("d3"))
+        | BitBoard::from_alg("d2")
+        | BitBoard::from_alg("d4")
+        | BitBoard::from_alg("d5");
+    assert_eq!(attacks, expected);
+    assert_eq!(board.attacks_from(Square::from_alg("a1")), EMPTY_BOARD);
+
+    let attacks = board.xray_attacks_from(
+        Square::from_alg("d1"),
+        BitBoard::from_alg("d2") | BitBoard::from_alg("d3"),
+    );
+    let expected = BitBoard::from_alg("a1")
+        | BitBoard::from_alg("b1")
+        | BitBoard::from_alg("c1")
+        | BitBoard::from_alg("e1")
+        | BitBoard::from_alg("d2")
+        | BitBoard::from_alg("d3")
+        | BitBoard::from_alg("d4")
+        | BitBoard::from_alg("d5");
+    assert_eq!(attacks, expected);
+}
+
+#[test]
+fn test_attacks_by() {
+    let fen = DEFAULT_FEN;
+    let board = Board::try_from_fen(fen).unwrap();
+    let expected =
+        (BitBoard::from(Row::new(0)) | BitBoard::from(Row::new(1)) | BitBoard::from(Row::new(2)))
+            ^ BitBoard::from_alg("a1")
+            ^ BitBoard::from_alg("h1");
+    assert_eq!(board.attacks_by(Color::White), expected);
+}
+
 impl Board {
     fn validate(&self) {
         assert!(self.white_occupied ^ self.black_occupied == self.occupied);
@@ -273,3 +345,18 @@ impl Board {
         }
     }
 }
+
+#[test]
+fn test_fen_rejects_bad_rows() {
+    for fen in [
+        "rnbqkbnrr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR",
+        "9/8/8/8/8/8/8/8",
+        "44p/8/8/8/8/8/8/8",
+        "0/8/8/8/8/8/8/8",
+        "7/8/8/8/8/8/8/8",
+        "ppppppp/8/8/8/8/8/8/8",
+    ] {
+        assert!(Board::try_from_fen(fen).is_err(), "{fen}");
+    }
+    assert!(Board::try_from_fen("4k3/8/8/8/8/8/8/4K3").is_ok());
+}
@@ -0,0 +1,566 @@
+mod params;
+mod trace;
+
+use crate::{
+    board::{BitBoard, Board, Column, Square},
+    gamestate::GameState,
+    pieces::{Color, Figure, Piece},
+};
+use alloc::vec::Vec;
+use core::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+pub use params::DEFAULT_PARAMS;
+pub use trace::{trace, EvalTrace, Term};
+
+pub const MAX_PHASE: i32 = 24;
+// indexed by figure, a full set of pieces adds up to MAX_PHASE
+const PHASE_WEIGHTS: [i32; 6] = [0, 2, 1, 1, 4, 0];
+const FILE_A: u64 = 0x0101_0101_0101_0101;
+const PIECE_FIGURES: [Figure; 4] = [Figure::Knight, Figure::Bishop, Figure::Rook, Figure::Queen];
+
+// default piece-square values by piece, mirrored for black
+static PIECE_SQUARES: [[Score; 64]; 12] = gen_piece_squares();
+static ADJACENT_FILES: [BitBoard; 8] = gen_adjacent_files();
+// squares in front of a pawn on its own and the adjacent files
+static PASSED_MASKS: [[BitBoard; 64]; 2] = [
+    gen_front_spans(Color::White, 7),
+    gen_front_spans(Color::Black, 7),
+];
+static SHIELD_MASKS: [[BitBoard; 64]; 2] = [
+    gen_front_spans(Color::White, 2),
+    gen_front_spans(Color::Black, 2),
+];
+
+// a middlegame and an endgame value, blended by the game phase
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct Score {
+    pub mg: i32,
+    pub eg: i32,
+}
+
+impl Score {
+    pub const ZERO: Self = Self::new(0, 0);
+
+    pub const fn new(mg: i32, eg: i32) -> Self {
+        Self { mg, eg }
+    }
+
+    pub const fn taper(self, phase: i32) -> i32 {
+        (self.mg * phase + self.eg * (MAX_PHASE - phase)) / MAX_PHASE
+    }
+}
+
+impl Add for Score {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self::Output {
+        Self::new(self.mg + rhs.mg, self.eg + rhs.eg)
+    }
+}
+
+impl AddAssign for Score {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for Score {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self::new(self.mg - rhs.mg, self.eg - rhs.eg)
+    }
+}
+
+impl SubAssign for Score {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl Mul<i32> for Score {
+    type Output = Self;
+    fn mul(self, rhs: i32) -> Self::Output {
+        Self::new(self.mg * rhs, self.eg * rhs)
+    }
+}
+
+impl Neg for Score {
+    type Output = Self;
+    fn neg(self) -> Self::Output {
+        Self::new(-self.mg, -self.eg)
+    }
+}
+
+// anything the weights can be summed as, plain scores for evaluating or the
+// coefficient vectors the tuner builds
+pub trait EvalValue:
+    Clone + Default + Add<Output = Self> + AddAssign + Sub<Output = Self> + Mul<i32, Output = Self>
+{
+}
+
+impl<T> EvalValue for T where
+    T: Clone + Default + Add<Output = T> + AddAssign + Sub<Output = T> + Mul<i32, Output = T>
+{
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EvalParams<T = Score> {
+    pub material: [T; 6],
+    pub pst: [[T; 64]; 6],
+    // per square reached, not counting squares attacked by enemy pawns
+    pub mobility: [T; 6],
+    pub doubled_pawn: T,
+    pub isolated_pawn: T,
+    pub connected_pawn: T,
+    // by row from the pawn's side
+    pub passed_pawn: [T; 8],
+    // per attacked square around the enemy king
+    pub king_attack: [T; 6],
+    pub pawn_shield: T,
+    pub bishop_pair: T,
+    pub rook_open_file: T,
+    pub rook_semi_open_file: T,
+    pub tempo: T,
+}
+
+impl<T> EvalParams<T> {
+    // visits every weight in declaration order
+    pub fn map<U>(&self, mut f: impl FnMut(&T) -> U) -> EvalParams<U> {
+        EvalParams {
+            material: self.material.each_ref().map(&mut f),
+            pst: self
+                .pst
+                .each_ref()
+                .map(|table| table.each_ref().map(&mut f)),
+            mobility: self.mobility.each_ref().map(&mut f),
+            doubled_pawn: f(&self.doubled_pawn),
+            isolated_pawn: f(&self.isolated_pawn),
+            connected_pawn: f(&self.connected_pawn),
+            passed_pawn: self.passed_pawn.each_ref().map(&mut f),
+            king_attack: self.king_attack.each_ref().map(&mut f),
+            pawn_shield: f(&self.pawn_shield),
+            bishop_pair: f(&self.bishop_pair),
+            rook_open_file: f(&self.rook_open_file),
+            rook_semi_open_file: f(&self.rook_semi_open_file),
+            tempo: f(&self.tempo),
+        }
+    }
+
+    pub fn to_vec(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        let mut values = Vec::new();
+        self.map(|value| values.push(value.clone()));
+        values
+    }
+}
+
+impl Default for EvalParams {
+    fn default() -> Self {
+        DEFAULT_PARAMS
+    }
+}
+
+// uses the network when one is loaded, otherwise the piece-square sums kept
+// by the board
+pub fn evaluate(game: &GameState) -> i32 {
+    let board = game.board();
+    let color = game.turn();
+    if let Some(nnue) = board.get_accumulator() {
+        return nnue.evaluate(color);
+    }
+    let score = evaluate_side(board, color, &DEFAULT_PARAMS)
+        - evaluate_side(board, !color, &DEFAULT_PARAMS)
+        + board.get_pst(color)
+        - board.get_pst(!color)
+        + DEFAULT_PARAMS.tempo;
+    score.taper(get_phase(board))
+}
+
+// score in centipawns from the side to move's point of view
+pub fn evaluate_with(game: &GameState, params: &EvalParams) -> i32 {
+    let board = game.board();
+    let color = game.turn();
+    let score =
+        evaluate_terms(board, color, params) - evaluate_terms(board, !color, params) + params.tempo;
+    score.taper(get_phase(board))
+}
+
+// every term for one side except the tempo, before tapering
+pub fn evaluate_terms<T: EvalValue>(board: &Board, color: Color, params: &EvalParams<T>) -> T {
+    evaluate_side(board, color, params) + piece_squares(board, color, params)
+}
+
+// MAX_PHASE with all pieces on the board down to 0 with only pawns and kings
+pub fn get_phase(board: &Board) -> i32 {
+    board.get_phase().min(MAX_PHASE)
+}
+
+pub(crate) fn get_piece_square(piece: Piece, square: Square) -> Score {
+    PIECE_SQUARES[u8::from(piece) as usize][square.as_usize()]
+}
+
+pub(crate) fn get_phase_weight(figure: Figure) -> i32 {
+    PHASE_WEIGHTS[figure as usize]
+}
+
+fn evaluate_side<T: EvalValue>(board: &Board, color: Color, params: &EvalParams<T>) -> T {
+    material(board, color, params)
+        + mobility(board, color, params)
+        + pawn_structure(board, color, params)
+        + king_safety(board, color, params)
+        + piece_bonuses(board, color, params)
+}
+
+fn material<T: EvalValue>(board: &Board, color: Color, params: &EvalParams<T>) -> T {
+    let mut score = T::default();
+    for figure in PIECE_FIGURES.into_iter().chain([Figure::Pawn]) {
+        score += params.material[figure as usize].clone() * get_count(board, color, figure);
+    }
+    score
+}
+
+fn piece_squares<T: EvalValue>(board: &Board, color: Color, params: &EvalParams<T>) -> T {
+    let mut score = T::default();
+    for square in board.get_color(color) {
+        if let Some(piece) = board.get_square(square) {
+            score += params.pst[piece.figure as usize][relative_square(square, color)].clone();
+        }
+    }
+    score
+}
+
+fn mobility<T: EvalValue>(board: &Board, color: Color, params: &EvalParams<T>) -> T {
+    let enemy_pawn_attacks = pawn_attacks(get_pieces(board, !color, Figure::Pawn), !color);
+    let mut score = T::default();
+    for figure in PIECE_FIGURES {
+        for square in get_pieces(board, color, figure) {
+            let count = (board.get_moves(square) & !enemy_pawn_attacks).pop_count() as i32;
+            score += params.mobility[figure as usize].clone() * count;
+        }
+    }
+    score
+}
+
+fn pawn_structure<T: EvalValue>(board: &Board, color: Color, params: &EvalParams<T>) -> T {
+    let pawns = get_pieces(board, color, Figure::Pawn);
+    let enemy_pawns = get_pieces(board, !color, Figure::Pawn);
+    let supported = pawns & pawn_attacks(pawns, color);
+    let mut score = T::default();
+    for square in pawns {
+        let col = square.get_col();
+        let neighbours = pawns & ADJACENT_FILES[col.as_u8() as usize];
+        if neighbours.is_empty() {
+            score += params.isolated_pawn.clone();
+        }
+        let phalanx = neighbours & square.get_row().as_bitboard();
+        if (supported & square.into()).is_not_empty() || phalanx.is_not_empty() {
+            score += params.connected_pawn.clone();
+        }
+        let front_span = PASSED_MASKS[color as usize][square.as_usize()];
+        // only the front pawn of a doubled pair counts as passed
+        let blocked_by_own = (pawns & front_span & col.as_bitboard()).is_not_empty();
+        if (enemy_pawns & front_span).is_empty() && !blocked_by_own {
+            score += params.passed_pawn[relative_row(square, color)].clone();
+        }
+    }
+    for col in 0..8 {
+        let count = (pawns & Column::new(col).as_bitboard()).pop_count() as i32;
+        if count > 1 {
+            score += params.doubled_pawn.clone() * (count - 1);
+        }
+    }
+    score
+}
+
+// penalises the side whose king is under attack
+fn king_safety<T: EvalValue>(board: &Board, color: Color, params: &EvalParams<T>) -> T {
+    let Some(king) = get_pieces(board, color, Figure::King).bitscan_forward() else {
+        return T::default();
+    };
+    let zone = king.get_king_moves() | king.into();
+    let mut score = T::default();
+    for figure in PIECE_FIGURES {
+        for square in get_pieces(board, !color, figure) {
+            let count = (board.attacks_from(square) & zone).pop_count() as i32;
+            score += params.king_attack[figure as usize].clone() * count;
+        }
+    }
+    let shield =
+        get_pieces(board, color, Figure::Pawn) & SHIELD_MASKS[color as usize][king.as_usize()];
+    score + params.pawn_shield.clone() * shield.pop_count() as i32
+}
+
+fn piece_bonuses<T: EvalValue>(board: &Board, color: Color, params: &EvalParams<T>) -> T {
+    let mut score = T::default();
+    if get_count(board, color, Figure::Bishop) >= 2 {
+        score += params.bishop_pair.clone();
+    }
+    let pawns = get_pieces(board, color, Figure::Pawn);
+    let all_pawns = pawns | get_pieces(board, !color, Figure::Pawn);
+    for square in get_pieces(board, color, Figure::Rook) {
+        let file = square.get_col().as_bitboard();
+        if (file & all_pawns).is_empty() {
+            score += params.rook_open_file.clone();
+        } else if (file & pawns).is_empty() {
+            score += params.rook_semi_open_file.clone();
+        }
+    }
+    score
+}
+
+fn get_pieces(board: &Board, color: Color, figure: Figure) -> BitBoard {
+    board.get_pieces(Piece { color, figure })
+}
+
+fn get_count(board: &Board, color: Color, figure: Figure) -> i32 {
+    board.get_piece_count(Piece { color, figure }) as i32
+}
+
+fn pawn_attacks(pawns: BitBoard, color: Color) -> BitBoard {
+    match color {
+        Color::White => pawns.gen_white_pawn_mask(),
+        Color::Black => pawns.gen_black_pawn_mask(),
+    }
+}
+
+fn relative_square(square: Square, color: Color) -> usize {
+    match color {
+        Color::White => square.as_usize(),
+        Color::Black => square.as_usize() ^ 56,
+    }
+}
+
+fn relative_row(square: Square, color: Color) -> usize {
+    relative_square(square, color) / 8
+}
+
+const fn gen_piece_squares() -> [[Score; 64]; 12] {
+    let mut array = [[Score::ZERO; 64]; 12];
+    let mut piece = 0;
+    while piece < 12 {
+        let mirror = match piece < 6 {
+            true => 0,
+            false => 56,
+        };
+        let mut square = 0;
+        while square < 64 {
+            array[piece][square] = DEFAULT_PARAMS.pst[piece % 6][square ^ mirror];
+            square += 1;
+        }
+        piece += 1;
+    }
+    array
+}
+
+const fn gen_adjacent_files() -> [BitBoard; 8] {
+    let mut array = [BitBoard::new(0); 8];
+    let mut col = 0;
+    while col < 8 {
+        let mut mask = 0;
+        if col > 0 {
+            mask |= FILE_A << (col - 1);
+        }
+        if col < 7 {
+            mask |= FILE_A << (col + 1);
+        }
+        array[col] = BitBoard::new(mask);
+        col += 1;
+    }
+    array
+}
+
+// squares up to `rows` rows in front of each square, on its file and the
+// adjacent ones
+const fn gen_front_spans(color: Color, rows: i32) -> [BitBoard; 64] {
+    let mut array = [BitBoard::new(0); 64];
+    let mut square = 0;
+    while square < 64 {
+        let (row, col) = (square / 8, square % 8);
+        let mut mask = 0;
+        let mut dist = 1;
+        while dist <= rows {
+            let target = match color {
+                Color::White => row + dist,
+                Color::Black => row - dist,
+            };
+            let mut target_col = col - 1;
+            while target_col <= col + 1 {
+                if target >= 0 && target < 8 && target_col >= 0 && target_col < 8 {
+                    mask |= 1 << (8 * target + target_col);
+                }
+                target_col += 1;
+            }
+            dist += 1;
+        }
+        array[square as usize] = BitBoard::new(mask);
+        square += 1;
+    }
+    array
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{string::String, vec::Vec};
+
+    // swaps the colours and flips the board vertically
+    fn mirror_fen(fen: &str) -> String {
+        let fields: Vec<&str> = fen.split(' ').collect();
+        let swap_case = |s: &str| -> String {
+            s.chars()
+                .map(|c| match c.is_ascii_uppercase() {
+                    true => c.to_ascii_lowercase(),
+                    false => c.to_ascii_uppercase(),
+                })
+                .collect()
+        };
+        let rows: Vec<&str> = fields[0].split('/').rev().collect();
+        let turn = match fields[1] {
+            "w" => "b",
+            _ => "w",
+        };
+        let ep: String = fields[3]
+            .chars()
+            .map(|c| match c {
+                '3' => '6',
+                '6' => '3',
+                c => c,
+            })
+            .collect();
+        alloc::format!(
+            "{} {} {} {} {} {}",
+            swap_case(&rows.join("/")),
+            turn,
+            swap_case(fields[2]),
+            ep,
+            fields[4],
+            fields[5]
+        )
+    }
+
+    #[test]
+    fn test_mirrored_positions_score_the_same() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        ] {
+            let game = GameState::try_from_fen(fen).unwrap();
+            let mirrored = GameState::try_from_fen(&mirror_fen(fen)).unwrap();
+            assert_eq!(evaluate(&game), evaluate(&mirrored), "{fen}");
+        }
+    }
+
+    #[test]
+    fn test_start_position_is_tempo() {
+        let game = GameState::default();
+        assert_eq!(get_phase(game.board()), MAX_PHASE);
+        assert_eq!(evaluate(&game), DEFAULT_PARAMS.tempo.mg);
+    }
+
+    #[test]
+    fn test_phase() {
+        let board = Board::try_from_fen("4k3/pppppppp/8/8/8/8/PPPPPPPP/4K3").unwrap();
+        assert_eq!(get_phase(&board), 0);
+        let board = Board::try_from_fen("3qk3/8/8/8/8/8/8/R3K2R").unwrap();
+        assert_eq!(get_phase(&board), 8);
+        // promoted pieces can push the count past the maximum
+        let board = Board::try_from_fen("qqqqk3/8/8/8/8/8/8/QQQQK3").unwrap();
+        assert_eq!(get_phase(&board), MAX_PHASE);
+        assert_eq!(Score::new(100, 50).taper(MAX_PHASE), 100);
+        assert_eq!(Score::new(100, 50).taper(0), 50);
+        assert_eq!(Score::new(100, 50).taper(MAX_PHASE / 2), 75);
+    }
+
+    #[test]
+    fn test_incremental_terms() {
+        fn check(game: &GameState) {
+            let board = game.board();
+            let mut phase = 0;
+            for color in [Color::White, Color::Black] {
+                assert_eq!(
+                    board.get_pst(color),
+                    piece_squares(board, color, &DEFAULT_PARAMS)
+                );
+                for figure in PIECE_FIGURES
+                    .into_iter()
+                    .chain([Figure::Pawn, Figure::King])
+                {
+                    let count = get_pieces(board, color, figure).pop_count();
+                    assert_eq!(board.get_piece_count(Piece { color, figure }), count);
+                    phase += PHASE_WEIGHTS[figure as usize] * count as i32;
+                }
+            }
+            assert_eq!(board.get_phase(), phase);
+            assert_eq!(evaluate(game), evaluate_with(game, &DEFAULT_PARAMS));
+        }
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let mut game = GameState::try_from_fen(fen).unwrap();
+        let start = game.board().clone();
+        check(&game);
+        // castling, en passant, captures and a promotion
+        for uci in [
+            "a2a4", "b4a3", "e1g1", "a3b2", "d5e6", "b2a1q", "e6f7", "e8d8",
+        ] {
+            game.make_move(game.parse_move(uci).unwrap());
+            check(&game);
+        }
+        while !game.history().is_empty() {
+            game.pop_move();
+            check(&game);
+        }
+        assert_eq!(game.board(), &start);
+    }
+
+    #[test]
+    fn test_pawn_structure() {
+        let params = EvalParams::default();
+        // doubled and isolated c pawns, f5 supported by e4, all three files passed
+        let board = Board::try_from_fen("4k3/8/8/5P2/2P1P3/2P5/8/4K3").unwrap();
+        let expected = params.doubled_pawn
+            + params.isolated_pawn * 2
+            + params.connected_pawn
+            + params.passed_pawn[4]
+            + params.passed_pawn[3]
+            + params.passed_pawn[3];
+        assert_eq!(pawn_structure(&board, Color::White, &params), expected);
+        // black pawns are scored from their own side
+        let board = Board::try_from_fen("4k3/8/8/8/8/5p2/8/4K3").unwrap();
+        let expected = params.isolated_pawn + params.passed_pawn[5];
+        assert_eq!(pawn_structure(&board, Color::Black, &params), expected);
+    }
+
+    #[test]
+    fn test_piece_bonuses() {
+        let params = EvalParams::default();
+        let board = Board::try_from_fen("4k3/p7/8/8/8/8/P3P3/R1BBK2R").unwrap();
+        let expected = params.bishop_pair + params.rook_open_file;
+        assert_eq!(piece_bonuses(&board, Color::White, &params), expected);
+        let board = Board::try_from_fen("4k3/8/8/8/8/8/4P3/R3K3").unwrap();
+        assert_eq!(
+            piece_bonuses(&board, Color::White, &params),
+            params.rook_open_file
+        );
+        let board = Board::try_from_fen("p3k3/8/8/8/8/8/4P3/R3K3").unwrap();
+        assert_eq!(
+            piece_bonuses(&board, Color::White, &params),
+            params.rook_semi_open_file
+        );
+    }
+
+    #[test]
+    fn test_king_safety() {
+        let params = EvalParams::default();
+        let board = Board::try_from_fen("4k3/8/8/8/8/8/5PPP/6K1").unwrap();
+        assert_eq!(
+            king_safety(&board, Color::White, &params),
+            params.pawn_shield * 3
+        );
+        // the queen on h4 hits f2 and h2 next to the king
+        let board = Board::try_from_fen("4k3/8/8/8/7q/8/5PPP/6K1").unwrap();
+        let expected = params.pawn_shield * 3 + params.king_attack[Figure::Queen as usize] * 2;
+        assert_eq!(king_safety(&board, Color::White, &params), expected);
+    }
+}
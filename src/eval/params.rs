@@ -0,0 +1,92 @@
+// can be regenerated with the tune binary
+use super::{EvalParams, Score};
+
+const fn s(mg: i32, eg: i32) -> Score {
+    Score::new(mg, eg)
+}
+
+// arrays over figures are in `Figure` order: pawn, rook, knight, bishop, queen, king
+// piece-square tables are from white's side with a1 first, black mirrors the rows
+#[rustfmt::skip]
+pub const DEFAULT_PARAMS: EvalParams = EvalParams {
+    material: [s(82, 94), s(477, 512), s(337, 281), s(365, 297), s(1025, 936), s(0, 0)],
+    pst: [
+        // pawn
+        [
+            s(0, 0), s(0, 0), s(0, 0), s(0, 0), s(0, 0), s(0, 0), s(0, 0), s(0, 0),
+            s(-10, 10), s(-5, 10), s(-5, 10), s(-15, 10), s(-15, 10), s(-5, 10), s(-5, 10), s(-10, 10),
+            s(-10, 5), s(-5, 5), s(-5, 5), s(-5, 5), s(-5, 5), s(-5, 5), s(-5, 5), s(-10, 5),
+            s(-5, 5), s(0, 5), s(0, 5), s(15, 5), s(15, 5), s(0, 5), s(0, 5), s(-5, 5),
+            s(5, 15), s(10, 15), s(10, 15), s(25, 15), s(25, 15), s(10, 15), s(10, 15), s(5, 15),
+            s(20, 30), s(25, 30), s(25, 30), s(25, 30), s(25, 30), s(25, 30), s(25, 30), s(20, 30),
+            s(55, 60), s(60, 60), s(60, 60), s(60, 60), s(60, 60), s(60, 60), s(60, 60), s(55, 60),
+            s(0, 0), s(0, 0), s(0, 0), s(0, 0), s(0, 0), s(0, 0), s(0, 0), s(0, 0),
+        ],
+        // rook
+        [
+            s(-3, 0), s(0, 0), s(0, 0), s(5, 0), s(5, 0), s(0, 0), s(0, 0), s(-3, 0),
+            s(-3, 0), s(0, 0), s(0, 0), s(5, 0), s(5, 0), s(0, 0), s(0, 0), s(-3, 0),
+            s(-3, 0), s(0, 0), s(0, 0), s(5, 0), s(5, 0), s(0, 0), s(0, 0), s(-3, 0),
+            s(-3, 0), s(0, 0), s(0, 0), s(5, 0), s(5, 0), s(0, 0), s(0, 0), s(-3, 0),
+            s(-3, 0), s(0, 0), s(0, 0), s(5, 0), s(5, 0), s(0, 0), s(0, 0), s(-3, 0),
+            s(-3, 0), s(0, 0), s(0, 0), s(5, 0), s(5, 0), s(0, 0), s(0, 0), s(-3, 0),
+            s(12, 10), s(15, 10), s(15, 10), s(20, 10), s(20, 10), s(15, 10), s(15, 10), s(12, 10),
+            s(-3, 0), s(0, 0), s(0, 0), s(5, 0), s(5, 0), s(0, 0), s(0, 0), s(-3, 0),
+        ],
+        // knight
+        [
+            s(-25, -20), s(-25, -20), s(-25, -20), s(-25, -20), s(-25, -20), s(-25, -20), s(-25, -20), s(-25, -20),
+            s(-25, -20), s(-13, -10), s(-13, -10), s(-13, -10), s(-13, -10), s(-13, -10), s(-13, -10), s(-25, -20),
+            s(-25, -20), s(-13, -10), s(-1, 0), s(-1, 0), s(-1, 0), s(-1, 0), s(-13, -10), s(-25, -20),
+            s(-25, -20), s(-13, -10), s(-1, 0), s(11, 10), s(11, 10), s(-1, 0), s(-13, -10), s(-25, -20),
+            s(-20, -20), s(-8, -10), s(4, 0), s(16, 10), s(16, 10), s(4, 0), s(-8, -10), s(-20, -20),
+            s(-20, -20), s(-8, -10), s(4, 0), s(4, 0), s(4, 0), s(4, 0), s(-8, -10), s(-20, -20),
+            s(-25, -20), s(-13, -10), s(-13, -10), s(-13, -10), s(-13, -10), s(-13, -10), s(-13, -10), s(-25, -20),
+            s(-25, -20), s(-25, -20), s(-25, -20), s(-25, -20), s(-25, -20), s(-25, -20), s(-25, -20), s(-25, -20),
+        ],
+        // bishop
+        [
+            s(-3, -8), s(-8, -8), s(-8, -8), s(-8, -8), s(-8, -8), s(-8, -8), s(-8, -8), s(-3, -8),
+            s(-8, -8), s(2, -4), s(-3, -4), s(-3, -4), s(-3, -4), s(-3, -4), s(2, -4), s(-8, -8),
+            s(-8, -8), s(-3, -4), s(7, 0), s(2, 0), s(2, 0), s(7, 0), s(-3, -4), s(-8, -8),
+            s(-8, -8), s(-3, -4), s(2, 0), s(12, 4), s(12, 4), s(2, 0), s(-3, -4), s(-8, -8),
+            s(-8, -8), s(-3, -4), s(2, 0), s(12, 4), s(12, 4), s(2, 0), s(-3, -4), s(-8, -8),
+            s(-8, -8), s(-3, -4), s(7, 0), s(2, 0), s(2, 0), s(7, 0), s(-3, -4), s(-8, -8),
+            s(-8, -8), s(2, -4), s(-3, -4), s(-3, -4), s(-3, -4), s(-3, -4), s(2, -4), s(-8, -8),
+            s(-3, -8), s(-8, -8), s(-8, -8), s(-8, -8), s(-8, -8), s(-8, -8), s(-8, -8), s(-3, -8),
+        ],
+        // queen
+        [
+            s(-5, -10), s(-5, -10), s(-5, -10), s(-5, -10), s(-5, -10), s(-5, -10), s(-5, -10), s(-5, -10),
+            s(-5, -10), s(-2, -4), s(-2, -4), s(-2, -4), s(-2, -4), s(-2, -4), s(-2, -4), s(-5, -10),
+            s(-5, -10), s(-2, -4), s(1, 2), s(1, 2), s(1, 2), s(1, 2), s(-2, -4), s(-5, -10),
+            s(-5, -10), s(-2, -4), s(1, 2), s(4, 8), s(4, 8), s(1, 2), s(-2, -4), s(-5, -10),
+            s(-5, -10), s(-2, -4), s(1, 2), s(4, 8), s(4, 8), s(1, 2), s(-2, -4), s(-5, -10),
+            s(-5, -10), s(-2, -4), s(1, 2), s(1, 2), s(1, 2), s(1, 2), s(-2, -4), s(-5, -10),
+            s(-5, -10), s(-2, -4), s(-2, -4), s(-2, -4), s(-2, -4), s(-2, -4), s(-2, -4), s(-5, -10),
+            s(-5, -10), s(-5, -10), s(-5, -10), s(-5, -10), s(-5, -10), s(-5, -10), s(-5, -10), s(-5, -10),
+        ],
+        // king
+        [
+            s(20, -30), s(30, -30), s(10, -30), s(0, -30), s(0, -30), s(10, -30), s(30, -30), s(20, -30),
+            s(10, -30), s(10, -15), s(-5, -15), s(-10, -15), s(-10, -15), s(-5, -15), s(10, -15), s(10, -30),
+            s(-20, -30), s(-20, -15), s(-20, 0), s(-20, 0), s(-20, 0), s(-20, 0), s(-20, -15), s(-20, -30),
+            s(-30, -30), s(-30, -15), s(-30, 0), s(-30, 15), s(-30, 15), s(-30, 0), s(-30, -15), s(-30, -30),
+            s(-40, -30), s(-40, -15), s(-40, 0), s(-40, 15), s(-40, 15), s(-40, 0), s(-40, -15), s(-40, -30),
+            s(-50, -30), s(-50, -15), s(-50, 0), s(-50, 0), s(-50, 0), s(-50, 0), s(-50, -15), s(-50, -30),
+            s(-60, -30), s(-60, -15), s(-60, -15), s(-60, -15), s(-60, -15), s(-60, -15), s(-60, -15), s(-60, -30),
+            s(-60, -30), s(-60, -30), s(-60, -30), s(-60, -30), s(-60, -30), s(-60, -30), s(-60, -30), s(-60, -30),
+        ],
+    ],
+    mobility: [s(0, 0), s(2, 4), s(4, 4), s(5, 5), s(1, 2), s(0, 0)],
+    doubled_pawn: s(-10, -20),
+    isolated_pawn: s(-10, -10),
+    connected_pawn: s(8, 5),
+    passed_pawn: [s(0, 0), s(5, 10), s(5, 15), s(10, 25), s(20, 45), s(35, 75), s(60, 110), s(0, 0)],
+    king_attack: [s(0, 0), s(-8, -2), s(-10, -2), s(-6, -2), s(-10, -4), s(0, 0)],
+    pawn_shield: s(12, 0),
+    bishop_pair: s(30, 50),
+    rook_open_file: s(25, 10),
+    rook_semi_open_file: s(12, 6),
+    tempo: s(15, 5),
+};
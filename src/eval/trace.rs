@@ -0,0 +1,200 @@
+use super::{
+    get_phase, king_safety, material, mobility, pawn_structure, piece_bonuses, Score,
+    DEFAULT_PARAMS, MAX_PHASE,
+};
+use crate::{gamestate::GameState, pieces::Color};
+use core::fmt;
+
+const TERM_COUNT: usize = 7;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Term {
+    Material = 0,
+    PieceSquares,
+    Mobility,
+    PawnStructure,
+    KingSafety,
+    Pieces,
+    Tempo,
+}
+
+impl Term {
+    pub const ALL: [Self; TERM_COUNT] = [
+        Self::Material,
+        Self::PieceSquares,
+        Self::Mobility,
+        Self::PawnStructure,
+        Self::KingSafety,
+        Self::Pieces,
+        Self::Tempo,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Material => "Material",
+            Self::PieceSquares => "Piece squares",
+            Self::Mobility => "Mobility",
+            Self::PawnStructure => "Pawns",
+            Self::KingSafety => "King safety",
+            Self::Pieces => "Pieces",
+            Self::Tempo => "Tempo",
+        }
+    }
+}
+
+// every term of the default evaluation, each side scored from its own point
+// of view before tapering
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EvalTrace {
+    pub turn: Color,
+    pub terms: [[Score; 2]; TERM_COUNT],
+    pub phase: i32,
+    pub score: i32,
+}
+
+impl EvalTrace {
+    pub fn get_term(&self, term: Term, color: Color) -> Score {
+        self.terms[term as usize][color as usize]
+    }
+
+    pub fn get_total(&self, color: Color) -> Score {
+        Term::ALL.iter().fold(Score::ZERO, |total, &term| {
+            total + self.get_term(term, color)
+        })
+    }
+
+    // the final score from white's point of view
+    pub fn get_white_score(&self) -> i32 {
+        match self.turn {
+            Color::White => self.score,
+            Color::Black => -self.score,
+        }
+    }
+}
+
+pub fn trace(game: &GameState) -> EvalTrace {
+    let board = game.board();
+    let params = &DEFAULT_PARAMS;
+    let mut terms = [[Score::ZERO; 2]; TERM_COUNT];
+    for color in [Color::White, Color::Black] {
+        let side = [
+            material(board, color, params),
+            board.get_pst(color),
+            mobility(board, color, params),
+            pawn_structure(board, color, params),
+            king_safety(board, color, params),
+            piece_bonuses(board, color, params),
+            Score::ZERO,
+        ];
+        for (term, score) in terms.iter_mut().zip(side) {
+            term[color as usize] = score;
+        }
+    }
+    terms[Term::Tempo as usize][game.turn() as usize] = params.tempo;
+    let phase = get_phase(board);
+    let white = terms
+        .iter()
+        .fold(Score::ZERO, |total, term| total + term[0] - term[1]);
+    let score = match game.turn() {
+        Color::White => white.taper(phase),
+        Color::Black => (-white).taper(phase),
+    };
+    EvalTrace {
+        turn: game.turn(),
+        terms,
+        phase,
+        score,
+    }
+}
+
+fn write_row(f: &mut fmt::Formatter, name: &str, white: Score, black: Score) -> fmt::Result {
+    let total = white - black;
+    writeln!(
+        f,
+        "{:>13} | {:>5} {:>5} | {:>5} {:>5} | {:>5} {:>5}",
+        name, white.mg, white.eg, black.mg, black.eg, total.mg, total.eg
+    )
+}
+
+impl fmt::Display for EvalTrace {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        const RULE: &str = "--------------+-------------+-------------+------------";
+        writeln!(f, "         Term |    White    |    Black    |    Total")?;
+        writeln!(f, "              |    MG    EG |    MG    EG |    MG    EG")?;
+        writeln!(f, "{RULE}")?;
+        for term in Term::ALL {
+            write_row(
+                f,
+                term.name(),
+                self.get_term(term, Color::White),
+                self.get_term(term, Color::Black),
+            )?;
+        }
+        writeln!(f, "{RULE}")?;
+        write_row(
+            f,
+            "Total",
+            self.get_total(Color::White),
+            self.get_total(Color::Black),
+        )?;
+        writeln!(f)?;
+        writeln!(f, "Phase: {}/{MAX_PHASE} (middlegame weight)", self.phase)?;
+        write!(
+            f,
+            "Final evaluation: {} cp (white side), {} cp (side to move)",
+            self.get_white_score(),
+            self.score
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval::evaluate;
+    use alloc::string::ToString;
+
+    #[test]
+    fn test_trace_matches_evaluate() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 b kq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 b - - 0 1",
+        ] {
+            let game = GameState::try_from_fen(fen).unwrap();
+            let trace = trace(&game);
+            assert_eq!(trace.score, evaluate(&game), "{fen}");
+            assert_eq!(trace.phase, get_phase(game.board()));
+            assert_eq!(
+                trace.get_term(Term::Tempo, game.turn()),
+                DEFAULT_PARAMS.tempo
+            );
+            assert_eq!(trace.get_term(Term::Tempo, !game.turn()), Score::ZERO);
+        }
+    }
+
+    #[test]
+    fn test_trace_table() {
+        let game = GameState::try_from_fen("4k3/8/8/8/8/8/4P3/4K3 b - - 0 1").unwrap();
+        let trace = trace(&game);
+        assert_eq!(trace.get_white_score(), -trace.score);
+        let table = trace.to_string();
+        for term in Term::ALL {
+            assert!(table.contains(term.name()));
+        }
+        let material = DEFAULT_PARAMS.material[0];
+        assert!(table.contains(&alloc::format!(
+            "Material | {:>5} {:>5} |     0     0 | {:>5} {:>5}",
+            material.mg,
+            material.eg,
+            material.mg,
+            material.eg
+        )));
+        assert!(table.ends_with(&alloc::format!(
+            "{} cp (white side), {} cp (side to move)",
+            -trace.score,
+            trace.score
+        )));
+    }
+}
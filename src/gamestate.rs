@@ -1,26 +1,44 @@
 mod castlerights;
+mod draw;
 mod moves;
+mod packed;
+mod see;
 
-use std::num::NonZeroU32;
+use alloc::{
+    boxed::Box,
+    string::{String, ToString},
+    sync::Arc,
+    vec::Vec,
+};
+use core::{fmt, num::NonZeroU32, str::FromStr};
 
 use crate::{
     board::{BitBoard, Board, Column, Row, Square, EMPTY_BOARD, FULL_BOARD},
+    nnue::Network,
     pieces::{
         constants::{BLACK_KING, WHITE_KING},
         Color, Figure, Piece,
     },
+    zobrist,
 };
-use castlerights::CastleRights;
-use moves::{Move, MoveIter};
+pub use castlerights::CastleRights;
+use moves::MoveIterator;
+pub use moves::{GenMode, Move};
+pub use packed::{GameResult, PackedRecord, PACKED_BYTES, RECORD_BYTES};
+#[cfg(feature = "std")]
+pub use packed::{PackedReader, PackedWriter};
+pub use see::SeeValues;
 
 pub const DEFAULT_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct MoveRecord {
     pub move_: Move,
     pub captured: Option<Piece>,
     pub castle_rights: CastleRights,
+    pub ep: Option<Square>,
     pub half_move: u16,
+    pub hash: u64,
 }
 
 impl MoveRecord {
@@ -28,18 +46,29 @@ impl MoveRecord {
         move_: Move,
         captured: Option<Piece>,
         castle_rights: CastleRights,
+        ep: Option<Square>,
         half_move: u16,
+        hash: u64,
     ) -> Self {
         Self {
             move_,
             captured,
             castle_rights,
+            ep,
             half_move,
+            hash,
         }
     }
 }
 
-#[derive(Debug)]
+// what make_null_move changed, to be handed back to unmake_null_move
+#[derive(Debug, Clone)]
+pub struct NullMoveRecord {
+    ep: Option<Square>,
+    half_moves: u16,
+}
+
+#[derive(Debug, Clone)]
 pub struct GameState {
     board: Board,
     turn: Color,
@@ -53,6 +82,46 @@ pub struct GameState {
 }
 
 impl GameState {
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    // evaluate with the network from now on, or with the hand-crafted
+    // evaluation when None
+    pub fn set_network(&mut self, network: Option<Arc<Network>>) {
+        self.board.set_network(network);
+    }
+
+    pub fn turn(&self) -> Color {
+        self.turn
+    }
+
+    pub fn castle_rights(&self) -> CastleRights {
+        self.castle
+    }
+
+    pub fn ep_square(&self) -> Option<Square> {
+        self.ep.map(|s| {
+            let row = match self.turn {
+                Color::White => s.get_row() + 1,
+                Color::Black => s.get_row() - 1,
+            };
+            Square::from_coords(row, s.get_col())
+        })
+    }
+
+    pub fn halfmove_clock(&self) -> u16 {
+        self.half_moves
+    }
+
+    pub fn fullmove_number(&self) -> u16 {
+        self.full_moves
+    }
+
+    pub fn history(&self) -> &[MoveRecord] {
+        &self.move_list
+    }
+
     pub fn get_king_sq(&self, color: Color) -> Square {
         match color {
             Color::White => self.white_king,
@@ -60,15 +129,31 @@ impl GameState {
         }
     }
 
+    pub fn get_hash(&self) -> u64 {
+        let mut hash =
+            self.board.get_hash() ^ zobrist::side_key(self.turn) ^ zobrist::castle_key(self.castle);
+        if let Some(ep) = self.ep {
+            hash ^= zobrist::ep_key(ep);
+        }
+        hash
+    }
+
+    pub fn is_check(&self) -> bool {
+        self.board
+            .is_attacked_by(self.get_king_sq(self.turn), !self.turn)
+    }
+
     pub fn is_legal(&self, move_: Move) -> bool {
         move_._is_legal(self)
     }
 
     pub fn make_move(&mut self, move_: Move) {
         let castle_rights = self.castle;
+        let ep = self.ep;
         let half_moves = self.half_moves;
+        let hash = self.get_hash();
         let captured = move_._make_move(self);
-        let record = MoveRecord::new(move_, captured, castle_rights, half_moves);
+        let record = MoveRecord::new(move_, captured, castle_rights, ep, half_moves, hash);
         self.move_list.push(record);
         if self.turn == Color::Black {
             self.full_moves += 1;
@@ -82,6 +167,7 @@ impl GameState {
         };
         prev_move.move_._unmake_move(self, prev_move.captured);
         self.castle = prev_move.castle_rights;
+        self.ep = prev_move.ep;
         self.half_moves = prev_move.half_move;
         if self.turn == Color::White {
             self.full_moves -= 1;
@@ -89,6 +175,55 @@ impl GameState {
         self.turn = !self.turn;
     }
 
+    // passes the turn, the halfmove clock restarts so repetition checks
+    // never look back across a null move
+    pub fn make_null_move(&mut self) -> NullMoveRecord {
+        let record = NullMoveRecord {
+            ep: self.ep.take(),
+            half_moves: core::mem::take(&mut self.half_moves),
+        };
+        if self.turn == Color::Black {
+            self.full_moves += 1;
+        }
+        self.turn = !self.turn;
+        record
+    }
+
+    pub fn unmake_null_move(&mut self, record: NullMoveRecord) {
+        self.ep = record.ep;
+        self.half_moves = record.half_moves;
+        if self.turn == Color::White {
+            self.full_moves -= 1;
+        }
+        self.turn = !self.turn;
+    }
+
+    pub fn legal_moves(&self) -> Vec<Move> {
+        self.generate_moves(GenMode::All)
+    }
+
+    pub fn generate_moves(&self, mode: GenMode) -> Vec<Move> {
+        self.board
+            .get_color(self.turn)
+            .flat_map(|square| MoveIterator::with_mode(self, square, mode))
+            .filter(|m| self.is_legal(*m))
+            .collect()
+    }
+
+    pub fn move_from_u16(&self, code: u16) -> Option<Move> {
+        let from = Square::try_from((code & 0x3f) as u8).ok()?;
+        MoveIterator::new(self, from)
+            .find(|m| m.to_u16() == code)
+            .filter(|m| self.is_legal(*m))
+    }
+
+    pub fn parse_move(&self, uci: &str) -> Result<Move, &'static str> {
+        self.legal_moves()
+            .into_iter()
+            .find(|m| m.to_uci().as_ref() == uci)
+            .ok_or("Illegal move")
+    }
+
     pub fn try_from_fen(fen: &str) -> Result<Self, &'static str> {
         let mut fen_iter = fen.split(' ');
         let position_fen = fen_iter.next().ok_or("Empty Fen")?;
@@ -100,15 +235,52 @@ impl GameState {
         };
         let castle_fen = fen_iter.next().ok_or("Empty Fen")?;
         let castle = CastleRights::try_from_fen(castle_fen)?;
-        let ep: Option<Square> = match fen_iter.next() {
+        let ep_target: Option<Square> = match fen_iter.next() {
             Some("-") => None,
             Some(coords) => Some(Square::try_from_alg(coords)?),
             None => return Err("Invalid Fen"),
         };
-        let half_moves = fen_iter.next().map(|x| x.parse::<u16>()).unwrap().unwrap();
-        let full_moves = fen_iter.next().map(|x| x.parse::<u16>()).unwrap().unwrap();
-        let white_king = board.get_pieces(WHITE_KING).iter_forward().next().unwrap();
-        let black_king = board.get_pieces(BLACK_KING).iter_forward().next().unwrap();
+        let half_moves = match fen_iter.next() {
+            Some(x) => x.parse::<u16>().map_err(|_| "Invalid halfmove clock")?,
+            None => 0,
+        };
+        let full_moves = match fen_iter.next() {
+            Some(x) => x.parse::<u16>().map_err(|_| "Invalid fullmove number")?,
+            None => 1,
+        };
+        Self::try_from_parts(board, turn, castle, ep_target, half_moves, full_moves)
+    }
+
+    pub(crate) fn try_from_parts(
+        board: Board,
+        turn: Color,
+        castle: CastleRights,
+        ep_target: Option<Square>,
+        half_moves: u16,
+        full_moves: u16,
+    ) -> Result<Self, &'static str> {
+        // the fen records the square behind the pawn, we track the pawn itself
+        let ep = match ep_target {
+            Some(target) => {
+                let row = match (turn, target.get_row().as_u8()) {
+                    (Color::White, 5) => Row::new(4),
+                    (Color::Black, 2) => Row::new(3),
+                    _ => return Err("Invalid en passant square"),
+                };
+                Some(Square::from_coords(row, target.get_col()))
+            }
+            None => None,
+        };
+        let white_king = board
+            .get_pieces(WHITE_KING)
+            .iter_forward()
+            .next()
+            .ok_or("Missing white king")?;
+        let black_king = board
+            .get_pieces(BLACK_KING)
+            .iter_forward()
+            .next()
+            .ok_or("Missing black king")?;
 
         Ok(Self {
             board,
@@ -139,11 +311,11 @@ impl GameState {
         fen.push(' ');
 
         // castle rights
-        fen.push_str(&self.castle.to_fen());
+        fen.push_str(self.castle.to_fen());
         fen.push(' ');
 
         // en passant
-        match self.ep {
+        match self.ep_square() {
             Some(s) => fen.push_str(&s.to_alg()),
             None => fen.push('-'),
         }
@@ -162,12 +334,7 @@ impl GameState {
     pub fn perft(&mut self, depth: u32) -> u128 {
         fn perft_(game: &mut GameState, depth: u32) -> u128 {
             let mut perft = 0;
-            let move_list: Vec<Move> = game
-                .board
-                .get_color(game.turn)
-                .flat_map(|square| MoveIter::new(game, square))
-                .filter(|m| game.is_legal(*m))
-                .collect();
+            let move_list = game.legal_moves();
             if depth == 1 {
                 return move_list.len() as u128;
             }
@@ -184,6 +351,7 @@ impl GameState {
         perft_(self, depth)
     }
 
+    #[cfg(feature = "std")]
     fn validate_position(&self) {
         let white_king = self.board.get_pieces(WHITE_KING).iter_forward().next();
         assert_eq!(white_king, Some(self.white_king));
@@ -202,6 +370,19 @@ impl GameState {
     }
 }
 
+impl fmt::Display for GameState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_fen())
+    }
+}
+
+impl FromStr for GameState {
+    type Err = &'static str;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::try_from_fen(s)
+    }
+}
+
 impl Default for GameState {
     fn default() -> Self {
         Self::try_from_fen(DEFAULT_FEN).unwrap()
@@ -211,6 +392,14 @@ impl Default for GameState {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{board::squares::*, pieces::constants::*};
+
+    #[test]
+    fn test_send_sync() {
+        // search threads each take a copy of the position
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<GameState>();
+    }
 
     #[test]
     fn test_fen() {
@@ -222,4 +411,152 @@ mod tests {
         assert_eq!(gs.half_moves, 0);
         assert_eq!(gs.full_moves, 1);
     }
+
+    #[test]
+    fn test_accessors() {
+        let fen = "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b Kq e3 0 3";
+        let mut gs: GameState = fen.parse().unwrap();
+        assert_eq!(gs.turn(), Color::Black);
+        assert_eq!(
+            gs.castle_rights(),
+            CastleRights::new(true, false, false, true)
+        );
+        assert_eq!(gs.ep_square(), Some(E3));
+        assert_eq!(gs.halfmove_clock(), 0);
+        assert_eq!(gs.fullmove_number(), 3);
+        assert_eq!(gs.board().get_square(E4), Some(WHITE_PAWN));
+        assert_eq!(gs.to_string(), fen);
+
+        let move_ = gs.parse_move("d4e3").unwrap();
+        assert!(matches!(move_, Move::EnPassant { .. }));
+        gs.make_move(move_);
+        assert_eq!(gs.history().len(), 1);
+        assert_eq!(gs.history()[0].move_, move_);
+        assert_eq!(gs.ep_square(), None);
+        assert_eq!(gs.fullmove_number(), 4);
+        assert!(gs.parse_move("e2e4").is_err());
+    }
+
+    #[test]
+    fn test_move_uci() {
+        let fen = "r3k2r/1P6/8/8/8/8/8/R3K2R w KQkq - 0 1";
+        let gs = GameState::try_from_fen(fen).unwrap();
+        let castle = gs.parse_move("e1g1").unwrap();
+        assert_eq!(
+            castle,
+            Move::KingSideCastle {
+                color: Color::White
+            }
+        );
+        let castle = gs.parse_move("e1c1").unwrap();
+        assert_eq!(
+            castle,
+            Move::QueenSideCastle {
+                color: Color::White
+            }
+        );
+        let promotion = gs.parse_move("b7a8n").unwrap();
+        assert_eq!(promotion.get_promotion(), Some(WHITE_KNIGHT));
+        assert_eq!(promotion.to_string(), "b7a8n");
+        assert_eq!(promotion.get_from(), B7);
+        assert_eq!(promotion.get_to(), A8);
+    }
+
+    #[test]
+    fn test_incremental_hash() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let mut gs = GameState::try_from_fen(fen).unwrap();
+        let start_hash = gs.get_hash();
+        let mut hashes = vec![start_hash];
+        for uci in ["a2a4", "b4a3", "e1g1", "a3b2", "d5e6", "b2a1q", "e6f7"] {
+            let move_ = gs.parse_move(uci).unwrap();
+            gs.make_move(move_);
+            let from_fen = GameState::try_from_fen(&gs.to_fen()).unwrap();
+            assert_eq!(gs.get_hash(), from_fen.get_hash(), "{uci}");
+            assert!(!hashes.contains(&gs.get_hash()));
+            hashes.push(gs.get_hash());
+        }
+        while !gs.history().is_empty() {
+            gs.pop_move();
+        }
+        assert_eq!(gs.get_hash(), start_hash);
+    }
+
+    #[test]
+    fn test_generation_modes() {
+        fn check_modes(gs: &mut GameState, depth: u32) {
+            let all = gs.legal_moves();
+            let expected: Vec<Move> = all
+                .iter()
+                .copied()
+                .filter(|m| {
+                    matches!(m, Move::EnPassant { .. } | Move::PromotePawn { .. })
+                        || gs.board.get_square(m.get_to()).is_some()
+                })
+                .collect();
+            assert_eq!(gs.generate_moves(GenMode::Tactical), expected);
+            let mut split = gs.generate_moves(GenMode::Quiet);
+            assert!(split.iter().all(|m| !expected.contains(m)));
+            split.extend(expected);
+            assert_eq!(split.len(), all.len());
+            assert!(all.iter().all(|m| split.contains(m)));
+            if gs.is_check() {
+                assert_eq!(gs.generate_moves(GenMode::Evasions), all);
+            }
+            if depth == 0 {
+                return;
+            }
+            for move_ in all {
+                gs.make_move(move_);
+                check_modes(gs, depth - 1);
+                gs.pop_move();
+            }
+        }
+        for fen in [
+            DEFAULT_FEN,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        ] {
+            check_modes(&mut GameState::try_from_fen(fen).unwrap(), 2);
+        }
+    }
+
+    #[test]
+    fn test_move_u16() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let mut gs = GameState::try_from_fen(fen).unwrap();
+        gs.make_move(gs.parse_move("a2a4").unwrap());
+        let moves = gs.legal_moves();
+        for &move_ in &moves {
+            assert_ne!(move_.to_u16(), 0);
+            assert_eq!(gs.move_from_u16(move_.to_u16()), Some(move_));
+        }
+        let mut codes: Vec<u16> = moves.iter().map(|m| m.to_u16()).collect();
+        codes.sort();
+        codes.dedup();
+        assert_eq!(codes.len(), moves.len());
+        let other_side = gs.parse_move("b4a3").unwrap();
+        gs.make_move(other_side);
+        assert_eq!(gs.move_from_u16(other_side.to_u16()), None);
+        assert_eq!(gs.move_from_u16(0), None);
+    }
+
+    #[test]
+    fn test_null_move() {
+        let fen = "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 3 2";
+        let mut gs = GameState::try_from_fen(fen).unwrap();
+        let hash = gs.get_hash();
+        let record = gs.make_null_move();
+        assert_eq!(
+            gs.to_fen().as_ref(),
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 2"
+        );
+        assert_ne!(gs.get_hash(), hash);
+        assert_eq!(gs.repetitions(), 0);
+        gs.unmake_null_move(record);
+        assert_eq!(gs.to_fen().as_ref(), fen);
+        assert_eq!(gs.get_hash(), hash);
+    }
 }
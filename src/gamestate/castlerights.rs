@@ -1,4 +1,5 @@
 use crate::pieces::Color;
+use core::{fmt, str::FromStr};
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct CastleRights(u8);
@@ -85,3 +86,32 @@ impl CastleRights {
         }
     }
 }
+
+impl fmt::Display for CastleRights {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_fen())
+    }
+}
+
+impl FromStr for CastleRights {
+    type Err = &'static str;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::try_from_fen(s)
+    }
+}
+
+impl From<CastleRights> for u8 {
+    fn from(value: CastleRights) -> Self {
+        value.0
+    }
+}
+
+impl TryFrom<u8> for CastleRights {
+    type Error = &'static str;
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0..=0b1111 => Ok(Self(value)),
+            _ => Err("Invalid castle rights"),
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use super::GameState;
+use crate::{
+    board::BitBoard,
+    pieces::{constants::*, Piece},
+};
+
+const DARK_SQUARES: BitBoard = BitBoard::new(0xaa55_aa55_aa55_aa55);
+
+impl GameState {
+    pub fn is_draw(&self) -> bool {
+        self.is_fifty_move_draw() || self.is_insufficient_material() || self.repetitions() >= 2
+    }
+
+    pub fn is_fifty_move_draw(&self) -> bool {
+        self.half_moves >= 100
+    }
+
+    // number of earlier occurrences of the current position, only looking
+    // back as far as the last capture or pawn move
+    pub fn repetitions(&self) -> usize {
+        let hash = self.get_hash();
+        self.move_list
+            .iter()
+            .rev()
+            .take(self.half_moves as usize)
+            .skip(1)
+            .step_by(2)
+            .filter(|record| record.hash == hash)
+            .count()
+    }
+
+    pub fn is_insufficient_material(&self) -> bool {
+        let count = |list: [Piece; 2]| {
+            self.board.get_piece_count(list[0]) + self.board.get_piece_count(list[1])
+        };
+        let mating_material = count([WHITE_PAWN, BLACK_PAWN])
+            + count([WHITE_ROOK, BLACK_ROOK])
+            + count([WHITE_QUEEN, BLACK_QUEEN]);
+        if mating_material > 0 {
+            return false;
+        }
+        let knights = count([WHITE_KNIGHT, BLACK_KNIGHT]);
+        match knights + count([WHITE_BISHOP, BLACK_BISHOP]) {
+            0 | 1 => true,
+            // any number of bishops all on the same square colour
+            _ => {
+                let bishops =
+                    self.board.get_pieces(WHITE_BISHOP) | self.board.get_pieces(BLACK_BISHOP);
+                knights == 0
+                    && ((bishops & DARK_SQUARES).is_empty() || (bishops & !DARK_SQUARES).is_empty())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(game: &mut GameState, moves: &[&str]) {
+        for uci in moves {
+            let move_ = game.parse_move(uci).unwrap();
+            game.make_move(move_);
+        }
+    }
+
+    #[test]
+    fn test_repetitions() {
+        let mut game = GameState::default();
+        assert_eq!(game.repetitions(), 0);
+        play(&mut game, &["g1f3", "g8f6", "f3g1", "f6g8"]);
+        assert_eq!(game.repetitions(), 1);
+        assert!(!game.is_draw());
+        play(&mut game, &["g1f3", "g8f6", "f3g1"]);
+        assert_eq!(game.repetitions(), 1);
+        play(&mut game, &["f6g8"]);
+        assert_eq!(game.repetitions(), 2);
+        assert!(game.is_draw());
+        game.pop_move();
+        assert_eq!(game.repetitions(), 1);
+    }
+
+    #[test]
+    fn test_repetition_needs_same_rights() {
+        let mut game = GameState::try_from_fen("4k3/8/8/8/8/8/8/R3K3 w Q - 0 1").unwrap();
+        play(&mut game, &["e1e2", "e8d8", "e2e1", "d8e8"]);
+        assert_eq!(game.repetitions(), 0);
+        play(&mut game, &["e1e2", "e8d8", "e2e1", "d8e8"]);
+        assert_eq!(game.repetitions(), 1);
+    }
+
+    #[test]
+    fn test_fifty_move_draw() {
+        let game = GameState::try_from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 99 80").unwrap();
+        assert!(!game.is_fifty_move_draw());
+        let game = GameState::try_from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 100 80").unwrap();
+        assert!(game.is_fifty_move_draw());
+        assert!(game.is_draw());
+    }
+
+    #[test]
+    fn test_insufficient_material() {
+        for (fen, expected) in [
+            ("4k3/8/8/8/8/8/8/4K3 w - - 0 1", true),
+            ("4k3/8/8/8/8/8/8/4KN2 w - - 0 1", true),
+            ("4k3/8/8/8/8/8/8/4KB2 w - - 0 1", true),
+            ("4k3/8/8/8/8/8/8/2B1KB2 w - - 0 1", false),
+            ("4kb2/8/8/8/8/8/8/4K1B1 w - - 0 1", true),
+            ("4k3/8/8/8/8/8/8/4KNN1 w - - 0 1", false),
+            ("4k3/8/8/8/8/8/8/4KP2 w - - 0 1", false),
+            ("4k3/8/8/8/8/8/8/4KR2 w - - 0 1", false),
+        ] {
+            let game = GameState::try_from_fen(fen).unwrap();
+            assert_eq!(game.is_insufficient_material(), expected, "{fen}");
+        }
+    }
+}
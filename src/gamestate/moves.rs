@@ -1,30 +1,15 @@
 use super::CastleRights;
 use super::GameState;
-use crate::gamestate::moves::make_move::move_pawn_double;
 use crate::{
-    board::{BitBoard, Column, Row, Square},
+    board::{squares::*, BitBoard, Row, Square, FULL_BOARD},
     pieces::{Color, Figure, Piece},
 };
+use alloc::{boxed::Box, string::String};
+use core::fmt;
 
-const A1: Square = Square::from_coords(Row::new(0), Column::new(0));
-const C1: Square = Square::from_coords(Row::new(0), Column::new(2));
-const D1: Square = Square::from_coords(Row::new(0), Column::new(3));
-const E1: Square = Square::from_coords(Row::new(0), Column::new(4));
-const F1: Square = Square::from_coords(Row::new(0), Column::new(5));
-const G1: Square = Square::from_coords(Row::new(0), Column::new(6));
-const H1: Square = Square::from_coords(Row::new(0), Column::new(7));
-const A2: Square = Square::from_coords(Row::new(1), Column::new(0));
-const A3: Square = Square::from_coords(Row::new(2), Column::new(0));
-const A6: Square = Square::from_coords(Row::new(5), Column::new(0));
-const A7: Square = Square::from_coords(Row::new(6), Column::new(0));
-const A8: Square = Square::from_coords(Row::new(7), Column::new(0));
-const C8: Square = Square::from_coords(Row::new(7), Column::new(2));
-const D8: Square = Square::from_coords(Row::new(7), Column::new(3));
-const E8: Square = Square::from_coords(Row::new(7), Column::new(4));
-const F8: Square = Square::from_coords(Row::new(7), Column::new(5));
-const G8: Square = Square::from_coords(Row::new(7), Column::new(6));
-const H8: Square = Square::from_coords(Row::new(7), Column::new(7));
+const PROMOTIONS: [Figure; 4] = [Figure::Queen, Figure::Rook, Figure::Bishop, Figure::Knight];
 
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Move {
     MovePiece {
@@ -49,45 +34,182 @@ pub enum Move {
         to: Square,
         ep: Square,
     },
-    KingSideCastle,
-    QueenSideCastle,
+    KingSideCastle {
+        color: Color,
+    },
+    QueenSideCastle {
+        color: Color,
+    },
 }
 
 impl Move {
-    pub fn _is_legal(self, game: &GameState) -> bool {
+    pub fn get_from(self) -> Square {
+        match self {
+            Move::MovePiece { from, .. }
+            | Move::MoveKing { from, .. }
+            | Move::PromotePawn { from, .. }
+            | Move::MovePawnDouble { from, .. }
+            | Move::EnPassant { from, .. } => from,
+            Move::KingSideCastle { color } => get_kingside_castle_squares(color).king_from,
+            Move::QueenSideCastle { color } => get_queenside_castle_squares(color).king_from,
+        }
+    }
+
+    pub fn get_to(self) -> Square {
+        match self {
+            Move::MovePiece { to, .. }
+            | Move::MoveKing { to, .. }
+            | Move::PromotePawn { to, .. }
+            | Move::MovePawnDouble { to, .. }
+            | Move::EnPassant { to, .. } => to,
+            Move::KingSideCastle { color } => get_kingside_castle_squares(color).king_to,
+            Move::QueenSideCastle { color } => get_queenside_castle_squares(color).king_to,
+        }
+    }
+
+    pub fn get_promotion(self) -> Option<Piece> {
+        match self {
+            Move::PromotePawn { promotion, .. } => Some(promotion),
+            _ => None,
+        }
+    }
+
+    // from and to squares plus the promotion figure, 0 is never a valid move
+    pub fn to_u16(self) -> u16 {
+        let promotion = self.get_promotion().map_or(0, |p| p.figure as u16 + 1);
+        self.get_from().as_u8() as u16 | (self.get_to().as_u8() as u16) << 6 | promotion << 12
+    }
+
+    pub fn to_uci(self) -> Box<str> {
+        let mut notation = String::with_capacity(5);
+        notation.push_str(&self.get_from().to_alg());
+        notation.push_str(&self.get_to().to_alg());
+        if let Some(promotion) = self.get_promotion() {
+            let c: char = Piece {
+                color: Color::Black,
+                figure: promotion.figure,
+            }
+            .into();
+            notation.push(c);
+        }
+        notation.into_boxed_str()
+    }
+
+    pub(crate) fn _is_legal(self, game: &GameState) -> bool {
         use check_move::*;
         match self {
-            Move::MovePiece { from, to } => check_move_piece_legality(game, from, to),
+            Move::MovePiece { from, to }
+            | Move::PromotePawn { from, to, .. }
+            | Move::MovePawnDouble { from, to } => check_move_piece_legality(game, from, to),
             Move::MoveKing { from, to } => check_move_king_legality(game, from, to),
-            Move::MovePawnDouble { from, to } => todo!(),
-            _ => unimplemented!(),
+            Move::EnPassant { from, to, ep } => check_en_passant_legality(game, from, to, ep),
+            Move::KingSideCastle { .. } => check_kingside_castle_legality(game),
+            Move::QueenSideCastle { .. } => check_queenside_castle_legality(game),
         }
     }
 
-    pub fn _make_move(self, game: &mut GameState) -> Option<Piece> {
+    pub(crate) fn _make_move(self, game: &mut GameState) -> Option<Piece> {
         use make_move::*;
         match self {
             Move::MovePiece { from, to } => move_piece(game, from, to),
             Move::MoveKing { from, to } => move_king(game, from, to),
+            Move::PromotePawn {
+                from,
+                to,
+                promotion,
+            } => promote_pawn(game, from, to, promotion),
             Move::MovePawnDouble { from, to } => {
                 move_pawn_double(game, from, to);
                 None
             }
-            _ => unimplemented!(),
+            Move::EnPassant { from, to, ep } => en_passant(game, from, to, ep),
+            Move::KingSideCastle { color } => {
+                castle(game, get_kingside_castle_squares(color));
+                None
+            }
+            Move::QueenSideCastle { color } => {
+                castle(game, get_queenside_castle_squares(color));
+                None
+            }
         }
     }
 
-    pub fn _unmake_move(self, game: &mut GameState, captured: Option<Piece>) {
+    pub(crate) fn _unmake_move(self, game: &mut GameState, captured: Option<Piece>) {
         use unmake_move::*;
         match self {
             Move::MovePiece { from, to } => unmove_piece(game, from, to, captured),
             Move::MoveKing { from, to } => unmove_king(game, from, to, captured),
-            Move::MovePawnDouble { from, to } => todo!(),
-            _ => unimplemented!(),
+            Move::PromotePawn {
+                from,
+                to,
+                promotion,
+            } => unpromote_pawn(game, from, to, promotion, captured),
+            Move::MovePawnDouble { from, to } => unmove_piece(game, from, to, None),
+            Move::EnPassant { from, to, ep } => unmove_en_passant(game, from, to, ep, captured),
+            Move::KingSideCastle { color } => uncastle(game, get_kingside_castle_squares(color)),
+            Move::QueenSideCastle { color } => uncastle(game, get_queenside_castle_squares(color)),
         }
     }
 }
 
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_uci())
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+struct CastleSquares {
+    king_from: Square,
+    king_to: Square,
+    rook_from: Square,
+    rook_to: Square,
+    empty: BitBoard,
+    safe: [Square; 3],
+}
+
+fn get_kingside_castle_squares(color: Color) -> CastleSquares {
+    match color {
+        Color::White => CastleSquares {
+            king_from: E1,
+            king_to: G1,
+            rook_from: H1,
+            rook_to: F1,
+            empty: F1.as_bitboard() | G1.as_bitboard(),
+            safe: [E1, F1, G1],
+        },
+        Color::Black => CastleSquares {
+            king_from: E8,
+            king_to: G8,
+            rook_from: H8,
+            rook_to: F8,
+            empty: F8.as_bitboard() | G8.as_bitboard(),
+            safe: [E8, F8, G8],
+        },
+    }
+}
+
+fn get_queenside_castle_squares(color: Color) -> CastleSquares {
+    match color {
+        Color::White => CastleSquares {
+            king_from: E1,
+            king_to: C1,
+            rook_from: A1,
+            rook_to: D1,
+            empty: B1.as_bitboard() | C1.as_bitboard() | D1.as_bitboard(),
+            safe: [E1, D1, C1],
+        },
+        Color::Black => CastleSquares {
+            king_from: E8,
+            king_to: C8,
+            rook_from: A8,
+            rook_to: D8,
+            empty: B8.as_bitboard() | C8.as_bitboard() | D8.as_bitboard(),
+            safe: [E8, D8, C8],
+        },
+    }
+}
+
 mod check_move {
     use super::*;
 
@@ -104,6 +226,45 @@ mod check_move {
         let safe_mask = game.board.get_safe_squares(from, game.turn);
         to & safe_mask == to
     }
+
+    pub fn check_en_passant_legality(
+        game: &GameState,
+        from: Square,
+        to: Square,
+        ep: Square,
+    ) -> bool {
+        let king_square = game.get_king_sq(game.turn);
+        let occupancy =
+            (game.board.get_occupied() ^ from.as_bitboard() ^ ep.as_bitboard()) | to.as_bitboard();
+        let attackers = game.board.attackers_to(king_square, occupancy)
+            & game.board.get_color(!game.turn)
+            & !ep.as_bitboard();
+        attackers.is_empty()
+    }
+
+    pub fn check_kingside_castle_legality(game: &GameState) -> bool {
+        game.castle.can_castle_kingside(game.turn)
+            && check_castle_squares(game, get_kingside_castle_squares(game.turn))
+    }
+
+    pub fn check_queenside_castle_legality(game: &GameState) -> bool {
+        game.castle.can_castle_queenside(game.turn)
+            && check_castle_squares(game, get_queenside_castle_squares(game.turn))
+    }
+
+    fn check_castle_squares(game: &GameState, squares: CastleSquares) -> bool {
+        let rook = Piece {
+            color: game.turn,
+            figure: Figure::Rook,
+        };
+        game.get_king_sq(game.turn) == squares.king_from
+            && game.board.get_square(squares.rook_from) == Some(rook)
+            && (game.board.get_occupied() & squares.empty).is_empty()
+            && squares
+                .safe
+                .iter()
+                .all(|&square| !game.board.is_attacked_by(square, !game.turn))
+    }
 }
 
 mod make_move {
@@ -120,21 +281,13 @@ mod make_move {
             f if f == king_rook => game.castle.remove_kingside_castle_rights(game.turn),
             _ => (),
         }
-        let (queen_rook, king_rook) = match game.turn {
-            Color::White => (A8, H8),
-            Color::Black => (A1, H1),
-        };
-        match to {
-            f if f == queen_rook => game.castle.remove_queenside_castle_rights(!game.turn),
-            f if f == king_rook => game.castle.remove_kingside_castle_rights(!game.turn),
-            _ => (),
-        }
+        remove_captured_castle_rights(game, to);
         let captured = game.board.move_piece(from, to);
         if captured.is_some()
             || game
                 .board
                 .get_square(to)
-                .map_or(false, |p| p.figure == Figure::Pawn)
+                .is_some_and(|p| p.figure == Figure::Pawn)
         {
             game.half_moves = 0;
         } else {
@@ -146,15 +299,7 @@ mod make_move {
     pub fn move_king(game: &mut GameState, from: Square, to: Square) -> Option<Piece> {
         game.ep = None;
         game.castle.remove_castle_rights(game.turn);
-        let (queen_rook, king_rook) = match game.turn {
-            Color::White => (A8, H8),
-            Color::Black => (A1, H1),
-        };
-        match to {
-            f if f == queen_rook => game.castle.remove_queenside_castle_rights(!game.turn),
-            f if f == king_rook => game.castle.remove_kingside_castle_rights(!game.turn),
-            _ => (),
-        }
+        remove_captured_castle_rights(game, to);
         match game.turn {
             Color::White => game.white_king = to,
             Color::Black => game.black_king = to,
@@ -168,11 +313,55 @@ mod make_move {
         captured
     }
 
+    pub fn promote_pawn(
+        game: &mut GameState,
+        from: Square,
+        to: Square,
+        promotion: Piece,
+    ) -> Option<Piece> {
+        game.ep = None;
+        game.half_moves = 0;
+        remove_captured_castle_rights(game, to);
+        game.board.clear_square(from);
+        game.board.set_square(to, promotion)
+    }
+
     pub fn move_pawn_double(game: &mut GameState, from: Square, to: Square) {
         game.ep = Some(to);
         game.half_moves = 0;
         game.board.move_piece(from, to);
     }
+
+    pub fn en_passant(game: &mut GameState, from: Square, to: Square, ep: Square) -> Option<Piece> {
+        game.ep = None;
+        game.half_moves = 0;
+        game.board.move_piece(from, to);
+        game.board.clear_square(ep)
+    }
+
+    pub fn castle(game: &mut GameState, squares: CastleSquares) {
+        game.ep = None;
+        game.half_moves += 1;
+        game.castle.remove_castle_rights(game.turn);
+        match game.turn {
+            Color::White => game.white_king = squares.king_to,
+            Color::Black => game.black_king = squares.king_to,
+        }
+        game.board.move_piece(squares.king_from, squares.king_to);
+        game.board.move_piece(squares.rook_from, squares.rook_to);
+    }
+
+    fn remove_captured_castle_rights(game: &mut GameState, to: Square) {
+        let (queen_rook, king_rook) = match game.turn {
+            Color::White => (A8, H8),
+            Color::Black => (A1, H1),
+        };
+        match to {
+            f if f == queen_rook => game.castle.remove_queenside_castle_rights(!game.turn),
+            f if f == king_rook => game.castle.remove_kingside_castle_rights(!game.turn),
+            _ => (),
+        }
+    }
 }
 
 mod unmake_move {
@@ -194,78 +383,164 @@ mod unmake_move {
             game.board.set_square(to, captured);
         }
     }
+
+    pub fn unpromote_pawn(
+        game: &mut GameState,
+        from: Square,
+        to: Square,
+        promotion: Piece,
+        captured: Option<Piece>,
+    ) {
+        game.board.clear_square(to);
+        let pawn = Piece {
+            color: promotion.color,
+            figure: Figure::Pawn,
+        };
+        game.board.set_square(from, pawn);
+        if let Some(captured) = captured {
+            game.board.set_square(to, captured);
+        }
+    }
+
+    pub fn unmove_en_passant(
+        game: &mut GameState,
+        from: Square,
+        to: Square,
+        ep: Square,
+        captured: Option<Piece>,
+    ) {
+        game.board.move_piece(to, from);
+        if let Some(captured) = captured {
+            game.board.set_square(ep, captured);
+        }
+    }
+
+    pub fn uncastle(game: &mut GameState, squares: CastleSquares) {
+        game.board.move_piece(squares.rook_to, squares.rook_from);
+        game.board.move_piece(squares.king_to, squares.king_from);
+        match game.turn {
+            Color::White => game.black_king = squares.king_from,
+            Color::Black => game.white_king = squares.king_from,
+        }
+    }
 }
 
 #[derive(Debug)]
 pub enum MoveIterator<'a> {
-    PawnMoveIterator {
+    PawnMoves {
         from: Square,
         to: BitBoard,
         game: &'a GameState,
         check_double_flag: bool,
         ep_square: Option<Square>,
     },
-    PawnPromotionIterator {
+    PromotionMoves {
         from: Square,
         to: BitBoard,
-        game: &'a GameState,
-        next_promotion: Option<Figure>,
+        color: Color,
+        current: Option<Square>,
+        next_promotion: usize,
     },
-    KingMoveIterator {
+    KingMoves {
         from: Square,
         to: BitBoard,
-        game: &'a GameState,
+        color: Color,
+        kingside: bool,
+        queenside: bool,
     },
-    PieceMoveIterator {
+    PieceMoves {
         from: Square,
         to: BitBoard,
-        game: &'a GameState,
     },
-    EmptyIterator,
+    Empty,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GenMode {
+    All,
+    // captures and promotions only
+    Tactical,
+    // everything Tactical leaves out
+    Quiet,
+    // moves that can possibly get the king out of check
+    Evasions,
 }
 
 impl<'a> MoveIterator<'a> {
     pub fn new(game: &'a GameState, from: Square) -> Self {
+        Self::with_mode(game, from, GenMode::All)
+    }
+
+    pub fn with_mode(game: &'a GameState, from: Square, mode: GenMode) -> Self {
         let Some(piece) = game.board.get_square(from) else {
-            return Self::EmptyIterator;
+            return Self::Empty;
         };
         if piece.color != game.turn {
-            return Self::EmptyIterator;
+            return Self::Empty;
         }
+        let target_mask = match (mode, piece.figure) {
+            (GenMode::All, _) | (GenMode::Evasions, Figure::King) => FULL_BOARD,
+            (GenMode::Tactical, _) => game.board.get_color(!piece.color),
+            (GenMode::Quiet, _) => !game.board.get_occupied(),
+            (GenMode::Evasions, _) => game
+                .board
+                .get_check_stops(game.get_king_sq(piece.color), piece.color),
+        };
         match piece.figure {
-            Figure::Pawn => Self::PawnMoveIterator {
+            Figure::Pawn if mode == GenMode::Quiet && is_promotion_row(from, piece.color) => {
+                Self::Empty
+            }
+            Figure::Pawn if is_promotion_row(from, piece.color) => Self::PromotionMoves {
                 from,
-                to: game.board.get_moves(from),
-                game,
-                check_double_flag: true,
-                ep_square: game.ep,
+                to: game.board.get_moves(from)
+                    & match mode {
+                        // quiet promotions count as tactical
+                        GenMode::Tactical => FULL_BOARD,
+                        _ => target_mask,
+                    },
+                color: piece.color,
+                current: None,
+                next_promotion: 0,
             },
-            Figure::King => Self::KingMoveIterator {
+            Figure::Pawn => Self::PawnMoves {
                 from,
-                to: game.board.get_moves(from),
+                to: game.board.get_moves(from) & target_mask,
                 game,
+                check_double_flag: mode != GenMode::Tactical,
+                ep_square: match mode {
+                    GenMode::Quiet => None,
+                    _ => game.ep,
+                },
             },
-            _ => Self::PieceMoveIterator {
+            Figure::King => Self::KingMoves {
                 from,
-                to: game.board.get_moves(from),
-                game,
+                to: game.board.get_moves(from) & target_mask,
+                color: piece.color,
+                kingside: matches!(mode, GenMode::All | GenMode::Quiet)
+                    && game.castle.can_castle_kingside(game.turn),
+                queenside: matches!(mode, GenMode::All | GenMode::Quiet)
+                    && game.castle.can_castle_queenside(game.turn),
+            },
+            _ => Self::PieceMoves {
+                from,
+                to: game.board.get_moves(from) & target_mask,
             },
         }
     }
 }
 
-impl<'a> Iterator for MoveIterator<'a> {
+impl Iterator for MoveIterator<'_> {
     type Item = Move;
     fn next(&mut self) -> Option<Self::Item> {
         match self {
-            Self::PawnMoveIterator {
+            Self::PawnMoves {
                 from,
                 to,
                 game,
                 check_double_flag,
                 ep_square,
             } => {
-                if std::mem::take(check_double_flag) {
+                if core::mem::take(check_double_flag) {
                     let double_move = get_double_pawn_move(*from, game);
                     if double_move.is_some() {
                         return double_move;
@@ -288,54 +563,61 @@ impl<'a> Iterator for MoveIterator<'a> {
                 }
                 to.next().map(|to| Move::MovePiece { from: *from, to })
             }
-            Self::PieceMoveIterator { from, to, game: _ } => {
+            Self::PromotionMoves {
+                from,
+                to,
+                color,
+                current,
+                next_promotion,
+            } => loop {
+                if let Some(to_square) = *current {
+                    if let Some(&figure) = PROMOTIONS.get(*next_promotion) {
+                        *next_promotion += 1;
+                        return Some(Move::PromotePawn {
+                            from: *from,
+                            to: to_square,
+                            promotion: Piece {
+                                color: *color,
+                                figure,
+                            },
+                        });
+                    }
+                }
+                *current = Some(to.next()?);
+                *next_promotion = 0;
+            },
+            Self::PieceMoves { from, to } => {
                 to.next().map(|to| Move::MovePiece { from: *from, to })
             }
-            Self::KingMoveIterator { from, to, game: _ } => {
-                to.next().map(|to| Move::MoveKing { from: *from, to })
+            Self::KingMoves {
+                from,
+                to,
+                color,
+                kingside,
+                queenside,
+            } => {
+                if let Some(to) = to.next() {
+                    return Some(Move::MoveKing { from: *from, to });
+                }
+                if core::mem::take(kingside) {
+                    return Some(Move::KingSideCastle { color: *color });
+                }
+                if core::mem::take(queenside) {
+                    return Some(Move::QueenSideCastle { color: *color });
+                }
+                None
             }
-            Self::EmptyIterator => None,
-            _ => None,
-        }
-    }
-}
-
-#[derive(Debug)]
-pub struct MoveIter<'a> {
-    from_square: Square,
-    to_squares: BitBoard,
-    piece: Piece,
-    game: &'a GameState,
-}
-
-impl<'a> MoveIter<'a> {
-    pub fn new(game: &'a GameState, square: Square) -> Self {
-        Self {
-            from_square: square,
-            piece: game.board.get_square(square).unwrap(),
-            to_squares: game.board.get_moves(square),
-            game,
+            Self::Empty => None,
         }
     }
 }
 
-impl<'a> Iterator for MoveIter<'a> {
-    type Item = Move;
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.game.turn != self.piece.color {
-            return None;
-        }
-        match self.piece.figure {
-            Figure::King => self.to_squares.next().map(|to| Move::MoveKing {
-                from: self.from_square,
-                to,
-            }),
-            _ => self.to_squares.next().map(|to| Move::MovePiece {
-                from: self.from_square,
-                to,
-            }),
+fn is_promotion_row(from: Square, color: Color) -> bool {
+    from.get_row().as_u8()
+        == match color {
+            Color::White => 6,
+            Color::Black => 1,
         }
-    }
 }
 
 fn get_double_pawn_move(from: Square, game: &GameState) -> Option<Move> {
@@ -347,12 +629,11 @@ fn get_double_pawn_move(from: Square, game: &GameState) -> Option<Move> {
     {
         return None;
     }
-    let double_mask: BitBoard = BitBoard::from(A2) | BitBoard::from(A3);
-    let block_mask = game.board.get_occupied()
-        & match game.turn {
-            Color::White => double_mask << from.get_col().as_u8(),
-            Color::Black => double_mask << 16 + from.get_col().as_u8(),
-        };
+    let double_mask = match game.turn {
+        Color::White => BitBoard::from(A3) | BitBoard::from(A4),
+        Color::Black => BitBoard::from(A5) | BitBoard::from(A6),
+    };
+    let block_mask = game.board.get_occupied() & (double_mask << from.get_col().as_u8());
     if block_mask.is_not_empty() {
         return None;
     }
@@ -0,0 +1,383 @@
+use super::{CastleRights, GameState};
+use crate::{
+    board::{BitBoard, Board, Square},
+    pieces::{Color, Piece},
+};
+
+pub const PACKED_BYTES: usize = 32;
+pub const RECORD_BYTES: usize = PACKED_BYTES + 3;
+const MAX_PIECES: u32 = 32;
+const NO_EP: u8 = u8::MAX;
+
+// 8 bytes of occupancy, a nibble per occupied square in a1..h8 order holding
+// the piece code, then side to move and castling rights, the ep target square,
+// both clocks and two zero bytes. The move history is not kept.
+impl GameState {
+    pub fn encode(&self) -> [u8; PACKED_BYTES] {
+        let mut bytes = [0; PACKED_BYTES];
+        let occupied = self.board.get_occupied();
+        let occupancy = occupied
+            .iter_forward()
+            .fold(0u64, |occupancy, square| occupancy | 1 << square.as_u8());
+        bytes[..8].copy_from_slice(&occupancy.to_le_bytes());
+        for (index, square) in occupied.iter_forward().enumerate() {
+            let code = self.board.get_square(square).map_or(0, u8::from);
+            bytes[8 + index / 2] |= code << (4 * (index % 2));
+        }
+        bytes[24] = self.turn as u8 | u8::from(self.castle) << 1;
+        bytes[25] = self.ep_square().map_or(NO_EP, u8::from);
+        bytes[26..28].copy_from_slice(&self.half_moves.to_le_bytes());
+        bytes[28..30].copy_from_slice(&self.full_moves.to_le_bytes());
+        bytes
+    }
+
+    pub fn decode(bytes: &[u8; PACKED_BYTES]) -> Result<Self, &'static str> {
+        let occupancy = u64::from_le_bytes(bytes[..8].try_into().unwrap());
+        let occupied = BitBoard::new(occupancy);
+        if occupied.pop_count() > MAX_PIECES {
+            return Err("Too many pieces");
+        }
+        let mut board = Board::default();
+        for (index, square) in occupied.iter_forward().enumerate() {
+            let code = bytes[8 + index / 2] >> (4 * (index % 2)) & 0xf;
+            board.set_square(square, Piece::try_from(code)?);
+        }
+        // the nibbles after the last piece are padding
+        let padding = (occupied.pop_count() as usize..MAX_PIECES as usize)
+            .any(|index| bytes[8 + index / 2] >> (4 * (index % 2)) & 0xf != 0);
+        if padding {
+            return Err("Invalid packed position");
+        }
+        let turn = match bytes[24] & 1 {
+            0 => Color::White,
+            _ => Color::Black,
+        };
+        let castle = CastleRights::try_from(bytes[24] >> 1)?;
+        let ep_target = match bytes[25] {
+            NO_EP => None,
+            code => Some(Square::try_from(code)?),
+        };
+        let half_moves = u16::from_le_bytes([bytes[26], bytes[27]]);
+        let full_moves = u16::from_le_bytes([bytes[28], bytes[29]]);
+        if bytes[30..] != [0, 0] {
+            return Err("Invalid packed position");
+        }
+        Self::try_from_parts(board, turn, castle, ep_target, half_moves, full_moves)
+    }
+}
+
+// the result of a game in half points for white
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GameResult {
+    BlackWin = 0,
+    Draw = 1,
+    WhiteWin = 2,
+}
+
+impl GameResult {
+    pub fn win_for(color: Color) -> Self {
+        match color {
+            Color::White => Self::WhiteWin,
+            Color::Black => Self::BlackWin,
+        }
+    }
+
+    pub fn get_points(self) -> f32 {
+        self as u8 as f32 / 2.0
+    }
+}
+
+impl TryFrom<u8> for GameResult {
+    type Error = &'static str;
+
+    fn try_from(code: u8) -> Result<Self, Self::Error> {
+        match code {
+            0 => Ok(Self::BlackWin),
+            1 => Ok(Self::Draw),
+            2 => Ok(Self::WhiteWin),
+            _ => Err("Invalid game result"),
+        }
+    }
+}
+
+// a packed position followed by its search score for white as a little endian
+// i16 and the result of the game it was played in, as datagen writes them
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PackedRecord {
+    pub position: [u8; PACKED_BYTES],
+    pub score: i16,
+    pub result: GameResult,
+}
+
+impl PackedRecord {
+    // the score is from the side to move's point of view
+    pub fn new(game: &GameState, score: i32, result: GameResult) -> Self {
+        let score = match game.turn() {
+            Color::White => score,
+            Color::Black => -score,
+        };
+        Self {
+            position: game.encode(),
+            score: score.clamp(i16::MIN as i32, i16::MAX as i32) as i16,
+            result,
+        }
+    }
+
+    pub fn to_bytes(&self) -> [u8; RECORD_BYTES] {
+        let mut bytes = [0; RECORD_BYTES];
+        bytes[..PACKED_BYTES].copy_from_slice(&self.position);
+        bytes[PACKED_BYTES..PACKED_BYTES + 2].copy_from_slice(&self.score.to_le_bytes());
+        bytes[PACKED_BYTES + 2] = self.result as u8;
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8; RECORD_BYTES]) -> Result<Self, &'static str> {
+        Ok(Self {
+            position: bytes[..PACKED_BYTES].try_into().unwrap(),
+            score: i16::from_le_bytes([bytes[PACKED_BYTES], bytes[PACKED_BYTES + 1]]),
+            result: GameResult::try_from(bytes[PACKED_BYTES + 2])?,
+        })
+    }
+
+    pub fn get_game(&self) -> Result<GameState, &'static str> {
+        GameState::decode(&self.position)
+    }
+}
+
+#[cfg(feature = "std")]
+pub use self::io::{PackedReader, PackedWriter};
+
+// positions back to back, each optionally followed by a fixed amount of data
+// such as a score or a game result
+#[cfg(feature = "std")]
+mod io {
+    use super::{GameState, PACKED_BYTES};
+    use std::io::{self, ErrorKind, Read, Write};
+
+    #[derive(Debug)]
+    pub struct PackedReader<R> {
+        inner: R,
+    }
+
+    impl<R: Read> PackedReader<R> {
+        pub fn new(inner: R) -> Self {
+            Self { inner }
+        }
+
+        pub fn into_inner(self) -> R {
+            self.inner
+        }
+
+        // fills `data` with whatever was stored after the position, returns
+        // None at the end of the stream
+        pub fn read_with(&mut self, data: &mut [u8]) -> io::Result<Option<GameState>> {
+            let mut bytes = [0; PACKED_BYTES];
+            if !self.fill(&mut bytes)? {
+                return Ok(None);
+            }
+            self.inner.read_exact(data)?;
+            GameState::decode(&bytes)
+                .map(Some)
+                .map_err(|err| io::Error::new(ErrorKind::InvalidData, err))
+        }
+
+        pub fn read(&mut self) -> io::Result<Option<GameState>> {
+            self.read_with(&mut [])
+        }
+
+        // false only when the stream ended cleanly before the first byte
+        fn fill(&mut self, buf: &mut [u8]) -> io::Result<bool> {
+            let mut filled = 0;
+            while filled < buf.len() {
+                match self.inner.read(&mut buf[filled..]) {
+                    Ok(0) if filled == 0 => return Ok(false),
+                    Ok(0) => return Err(ErrorKind::UnexpectedEof.into()),
+                    Ok(n) => filled += n,
+                    Err(err) if err.kind() == ErrorKind::Interrupted => (),
+                    Err(err) => return Err(err),
+                }
+            }
+            Ok(true)
+        }
+    }
+
+    impl<R: Read> Iterator for PackedReader<R> {
+        type Item = io::Result<GameState>;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            self.read().transpose()
+        }
+    }
+
+    #[derive(Debug)]
+    pub struct PackedWriter<W: Write> {
+        inner: W,
+    }
+
+    impl<W: Write> PackedWriter<W> {
+        pub fn new(inner: W) -> Self {
+            Self { inner }
+        }
+
+        pub fn into_inner(self) -> W {
+            self.inner
+        }
+
+        pub fn write_with(&mut self, game: &GameState, data: &[u8]) -> io::Result<()> {
+            self.inner.write_all(&game.encode())?;
+            self.inner.write_all(data)
+        }
+
+        pub fn write(&mut self, game: &GameState) -> io::Result<()> {
+            self.write_with(game, &[])
+        }
+
+        pub fn flush(&mut self) -> io::Result<()> {
+            self.inner.flush()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    const FENS: [&str; 6] = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+    ];
+
+    #[test]
+    fn test_round_trip_perft_positions() {
+        fn walk(game: &mut GameState, depth: u32) {
+            let bytes = game.encode();
+            let decoded = GameState::decode(&bytes).unwrap();
+            assert_eq!(decoded.encode(), bytes);
+            assert_eq!(decoded.board(), game.board());
+            assert_eq!(decoded.get_hash(), game.get_hash());
+            if depth == 0 {
+                return;
+            }
+            for move_ in game.legal_moves() {
+                game.make_move(move_);
+                walk(game, depth - 1);
+                game.pop_move();
+            }
+        }
+        for fen in FENS {
+            let mut game = GameState::try_from_fen(fen).unwrap();
+            walk(&mut game, 3);
+        }
+    }
+
+    #[test]
+    fn test_encode_layout() {
+        let game = GameState::try_from_fen("4k3/8/8/3pP3/8/8/8/R3K3 w Q d6 3 40").unwrap();
+        let bytes = game.encode();
+        let occupancy = u64::from_le_bytes(bytes[..8].try_into().unwrap());
+        assert_eq!(occupancy, 1 | 1 << 4 | 1 << 35 | 1 << 36 | 1 << 60);
+        // a1 rook and e1 king, then d5 and e5 pawns, then the e8 king
+        let code = |c| u8::from(Piece::try_from(c).unwrap());
+        assert_eq!(bytes[8], code('R') | code('K') << 4);
+        assert_eq!(bytes[9], code('p') | code('P') << 4);
+        assert_eq!(bytes[10], code('k'));
+        assert_eq!(bytes[24], 0b0100);
+        assert_eq!(bytes[25], Square::try_from_alg("d6").unwrap().as_u8());
+        assert_eq!(bytes[26..30], [3, 0, 40, 0]);
+        assert_eq!(bytes[30..], [0, 0]);
+    }
+
+    #[test]
+    fn test_decode_rejects_invalid() {
+        let bytes = GameState::default().encode();
+        let mut too_many = bytes;
+        too_many[..8].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(GameState::decode(&too_many).is_err());
+        let mut bad_piece = bytes;
+        bad_piece[8] = 0xff;
+        assert!(GameState::decode(&bad_piece).is_err());
+        let mut bad_ep = bytes;
+        bad_ep[25] = 64;
+        assert!(GameState::decode(&bad_ep).is_err());
+        let mut reserved = bytes;
+        reserved[31] = 1;
+        assert!(GameState::decode(&reserved).is_err());
+        // the default position fills every nibble, so drop the h8 rook and
+        // leave its nibble behind as padding
+        let mut missing_rook =
+            GameState::try_from_fen("rnbqkbn1/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQq - 0 1")
+                .unwrap()
+                .encode();
+        assert!(GameState::decode(&missing_rook).is_ok());
+        missing_rook[23] |= 0x90;
+        assert!(GameState::decode(&missing_rook).is_err());
+        let mut padding = GameState::try_from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1")
+            .unwrap()
+            .encode();
+        assert!(GameState::decode(&padding).is_ok());
+        padding[12] = 0x01;
+        assert!(GameState::decode(&padding).is_err());
+        let no_kings = [0; PACKED_BYTES];
+        assert!(GameState::decode(&no_kings).is_err());
+    }
+
+    #[test]
+    fn test_record() {
+        let game = GameState::try_from_fen("4k3/8/8/3pP3/8/8/8/R3K3 b - - 3 40").unwrap();
+        let record = PackedRecord::new(&game, 250, GameResult::WhiteWin);
+        // scores are stored for white
+        assert_eq!(record.score, -250);
+        let bytes = record.to_bytes();
+        assert_eq!(bytes[..PACKED_BYTES], game.encode());
+        assert_eq!(bytes[PACKED_BYTES..], [0x06, 0xff, 2]);
+        let decoded = PackedRecord::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded, record);
+        assert_eq!(decoded.get_game().unwrap().to_fen(), game.to_fen());
+        assert_eq!(decoded.result.get_points(), 1.0);
+        let mut bad_result = bytes;
+        bad_result[PACKED_BYTES + 2] = 3;
+        assert!(PackedRecord::from_bytes(&bad_result).is_err());
+        assert_eq!(GameResult::win_for(Color::Black), GameResult::BlackWin);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_reader_writer() {
+        let games: Vec<GameState> = FENS
+            .iter()
+            .map(|fen| GameState::try_from_fen(fen).unwrap())
+            .collect();
+        let mut writer = PackedWriter::new(Vec::new());
+        for (index, game) in games.iter().enumerate() {
+            writer.write_with(game, &[index as u8, 0xaa]).unwrap();
+        }
+        let bytes = writer.into_inner();
+        assert_eq!(bytes.len(), games.len() * (PACKED_BYTES + 2));
+
+        let mut reader = PackedReader::new(bytes.as_slice());
+        let mut data = [0; 2];
+        for (index, game) in games.iter().enumerate() {
+            let decoded = reader.read_with(&mut data).unwrap().unwrap();
+            assert_eq!(decoded.to_fen(), game.to_fen());
+            assert_eq!(data, [index as u8, 0xaa]);
+        }
+        assert!(reader.read_with(&mut data).unwrap().is_none());
+
+        let mut writer = PackedWriter::new(Vec::new());
+        games.iter().for_each(|game| writer.write(game).unwrap());
+        let bytes = writer.into_inner();
+        let fens: Vec<_> = PackedReader::new(bytes.as_slice())
+            .map(|game| game.unwrap().to_fen())
+            .collect();
+        assert_eq!(fens, games.iter().map(|g| g.to_fen()).collect::<Vec<_>>());
+        // a truncated record is an error rather than the end of the stream
+        let mut reader = PackedReader::new(&bytes[..PACKED_BYTES + 5]);
+        assert!(reader.next().unwrap().is_ok());
+        assert!(reader.next().unwrap().is_err());
+    }
+}
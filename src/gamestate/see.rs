@@ -0,0 +1,225 @@
+use super::{moves::Move, GameState};
+use crate::{
+    board::{BitBoard, Square},
+    pieces::{Color, Figure, Piece},
+};
+
+const SEE_ORDER: [Figure; 6] = [
+    Figure::Pawn,
+    Figure::Knight,
+    Figure::Bishop,
+    Figure::Rook,
+    Figure::Queen,
+    Figure::King,
+];
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SeeValues {
+    pub pawn: i32,
+    pub knight: i32,
+    pub bishop: i32,
+    pub rook: i32,
+    pub queen: i32,
+    pub king: i32,
+}
+
+impl SeeValues {
+    pub fn get_value(&self, figure: Figure) -> i32 {
+        match figure {
+            Figure::Pawn => self.pawn,
+            Figure::Rook => self.rook,
+            Figure::Knight => self.knight,
+            Figure::Bishop => self.bishop,
+            Figure::Queen => self.queen,
+            Figure::King => self.king,
+        }
+    }
+}
+
+impl Default for SeeValues {
+    fn default() -> Self {
+        Self {
+            pawn: 100,
+            knight: 320,
+            bishop: 330,
+            rook: 500,
+            queen: 900,
+            king: 20_000,
+        }
+    }
+}
+
+impl GameState {
+    pub fn see(&self, move_: Move) -> i32 {
+        self.see_with(move_, &SeeValues::default())
+    }
+
+    pub fn see_ge(&self, move_: Move, threshold: i32) -> bool {
+        self.see_ge_with(move_, threshold, &SeeValues::default())
+    }
+
+    pub fn see_ge_with(&self, move_: Move, threshold: i32, values: &SeeValues) -> bool {
+        self.see_with(move_, values) >= threshold
+    }
+
+    pub fn see_with(&self, move_: Move, values: &SeeValues) -> i32 {
+        let (from, to, captured, promotion) = match move_ {
+            Move::MovePiece { from, to }
+            | Move::MoveKing { from, to }
+            | Move::MovePawnDouble { from, to } => (from, to, self.board.get_square(to), None),
+            Move::PromotePawn {
+                from,
+                to,
+                promotion,
+            } => (from, to, self.board.get_square(to), Some(promotion)),
+            Move::EnPassant { from, to, ep } => (from, to, self.board.get_square(ep), None),
+            Move::KingSideCastle { .. } | Move::QueenSideCastle { .. } => return 0,
+        };
+        let Some(mover) = self.board.get_square(from) else {
+            return 0;
+        };
+        let mut occupancy = self.board.get_occupied() ^ from.as_bitboard();
+        if let Move::EnPassant { ep, .. } = move_ {
+            occupancy ^= ep.as_bitboard();
+        }
+
+        let mut gain = [0i32; 32];
+        gain[0] = captured.map_or(0, |p| values.get_value(p.figure));
+        let mut on_square = mover.figure;
+        if let Some(promotion) = promotion {
+            gain[0] += values.get_value(promotion.figure) - values.pawn;
+            on_square = promotion.figure;
+        }
+
+        let mut side = !mover.color;
+        let mut depth = 0;
+        while depth + 1 < gain.len() {
+            let attackers = self.board.attackers_to(to, occupancy) & self.board.get_color(side);
+            let Some((attacker_sq, figure)) = self.least_valuable_attacker(attackers, side) else {
+                break;
+            };
+            if figure == Figure::King {
+                let defenders = self
+                    .board
+                    .attackers_to(to, occupancy ^ attacker_sq.as_bitboard())
+                    & self.board.get_color(!side);
+                if defenders.is_not_empty() {
+                    break;
+                }
+            }
+            depth += 1;
+            gain[depth] = values.get_value(on_square) - gain[depth - 1];
+            on_square = figure;
+            if figure == Figure::Pawn && is_last_row(to, side) {
+                gain[depth] += values.queen - values.pawn;
+                on_square = Figure::Queen;
+            }
+            occupancy ^= attacker_sq.as_bitboard();
+            side = !side;
+        }
+        while depth > 0 {
+            gain[depth - 1] = -i32::max(-gain[depth - 1], gain[depth]);
+            depth -= 1;
+        }
+        gain[0]
+    }
+
+    fn least_valuable_attacker(
+        &self,
+        attackers: BitBoard,
+        color: Color,
+    ) -> Option<(Square, Figure)> {
+        SEE_ORDER.iter().find_map(|&figure| {
+            (attackers & self.board.get_pieces(Piece { color, figure }))
+                .bitscan_forward()
+                .map(|square| (square, figure))
+        })
+    }
+}
+
+fn is_last_row(square: Square, color: Color) -> bool {
+    square.get_row().as_u8()
+        == match color {
+            Color::White => 7,
+            Color::Black => 0,
+        }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find_move(game: &GameState, from: &str, to: &str) -> Move {
+        let from = Square::from_alg(from);
+        let to = Square::from_alg(to);
+        game.legal_moves()
+            .into_iter()
+            .find(|m| match *m {
+                Move::MovePiece { from: f, to: t }
+                | Move::MoveKing { from: f, to: t }
+                | Move::MovePawnDouble { from: f, to: t }
+                | Move::PromotePawn {
+                    from: f,
+                    to: t,
+                    promotion:
+                        Piece {
+                            figure: Figure::Queen,
+                            ..
+                        },
+                }
+                | Move::EnPassant { from: f, to: t, .. } => f == from && t == to,
+                _ => false,
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn test_see_undefended_capture() {
+        let fen = "1k1r4/1pp4p/p7/4p3/8/P5P1/1PP4P/2K1R3 w - - 0 1";
+        let game = GameState::try_from_fen(fen).unwrap();
+        let move_ = find_move(&game, "e1", "e5");
+        assert_eq!(game.see(move_), 100);
+        assert!(game.see_ge(move_, 100));
+        assert!(!game.see_ge(move_, 101));
+    }
+
+    #[test]
+    fn test_see_xray_exchange() {
+        let fen = "1k1r3q/1ppn3p/p4b2/4p3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1";
+        let game = GameState::try_from_fen(fen).unwrap();
+        let move_ = find_move(&game, "d3", "e5");
+        assert_eq!(game.see(move_), -220);
+        assert!(!game.see_ge(move_, 0));
+    }
+
+    #[test]
+    fn test_see_en_passant() {
+        let fen = "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1";
+        let game = GameState::try_from_fen(fen).unwrap();
+        let move_ = find_move(&game, "e5", "d6");
+        assert!(matches!(move_, Move::EnPassant { .. }));
+        assert_eq!(game.see(move_), 100);
+    }
+
+    #[test]
+    fn test_see_promotion() {
+        let fen = "r3k3/1P6/8/8/8/8/8/4K3 w - - 0 1";
+        let game = GameState::try_from_fen(fen).unwrap();
+        let move_ = find_move(&game, "b7", "b8");
+        assert_eq!(game.see(move_), -100);
+        let move_ = find_move(&game, "b7", "a8");
+        assert_eq!(game.see(move_), 1300);
+    }
+
+    #[test]
+    fn test_see_custom_values() {
+        let fen = "1k1r4/1pp4p/p7/4p3/8/P5P1/1PP4P/2K1R3 w - - 0 1";
+        let game = GameState::try_from_fen(fen).unwrap();
+        let move_ = find_move(&game, "e1", "e5");
+        let values = SeeValues {
+            pawn: 1,
+            ..SeeValues::default()
+        };
+        assert_eq!(game.see_with(move_, &values), 1);
+    }
+}
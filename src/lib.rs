@@ -1,12 +1,28 @@
 #![allow(dead_code)]
 #![allow(unused_imports)]
 #![allow(unreachable_code)]
+#![cfg_attr(not(feature = "std"), no_std)]
+extern crate alloc;
+
 mod board;
+pub mod eval;
 mod gamestate;
+pub mod mate;
+pub mod nnue;
 mod pieces;
+#[cfg(feature = "std")]
+pub mod search;
+#[cfg(feature = "serde")]
+mod serialization;
+mod zobrist;
 
-pub use gamestate::GameState;
-use tikv_jemallocator::Jemalloc;
-
-#[global_allocator]
-static GLOBAL: Jemalloc = Jemalloc;
+pub use board::{squares, BitBoard, Board, Column, Row, Square, EMPTY_BOARD, FULL_BOARD};
+pub use gamestate::{
+    CastleRights, GameResult, GameState, GenMode, Move, MoveRecord, PackedRecord, SeeValues,
+    DEFAULT_FEN, PACKED_BYTES, RECORD_BYTES,
+};
+#[cfg(feature = "std")]
+pub use gamestate::{PackedReader, PackedWriter};
+pub use pieces::{Color, Figure, Piece};
+#[cfg(feature = "serde")]
+pub use serialization::MoveSeed;
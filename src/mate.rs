@@ -0,0 +1,281 @@
+use crate::gamestate::{GameState, Move};
+use alloc::{collections::BTreeMap, vec::Vec};
+use core::cmp::Reverse;
+
+// an attacking move together with every defence against it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MateLine {
+    pub move_: Move,
+    // moves of the attacking side until mate, this one included
+    pub length: u32,
+    // the longest resistance first, empty when the move mates
+    pub defences: Vec<Defence>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Defence {
+    pub move_: Move,
+    // the fastest mate against it
+    pub answer: MateLine,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MateSolution {
+    // every first move that mates in time, fastest first. More than one
+    // means the problem is cooked.
+    pub keys: Vec<MateLine>,
+}
+
+impl MateSolution {
+    pub fn is_mate(&self) -> bool {
+        !self.keys.is_empty()
+    }
+
+    pub fn is_unique(&self) -> bool {
+        self.keys.len() == 1
+    }
+
+    pub fn get_key(&self) -> Option<&MateLine> {
+        self.keys.first()
+    }
+
+    pub fn get_cooks(&self) -> &[MateLine] {
+        self.keys.get(1..).unwrap_or_default()
+    }
+}
+
+// proves or refutes mate in n for the side to move by trying every defence.
+// Draws by repetition or the fifty move rule are not considered, problems
+// are too short for them to matter.
+#[derive(Debug, Default)]
+pub struct MateSolver {
+    // per position with the attacker to move: the fewest moves it is known
+    // to mate in and the most it is known not to
+    bounds: BTreeMap<u64, (u32, u32)>,
+    nodes: u64,
+}
+
+impl MateSolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get_nodes(&self) -> u64 {
+        self.nodes
+    }
+
+    pub fn clear(&mut self) {
+        self.bounds.clear();
+        self.nodes = 0;
+    }
+
+    pub fn is_mate_in(&mut self, game: &mut GameState, moves: u32) -> bool {
+        self.attack(game, moves)
+    }
+
+    // every key with its full solution tree
+    pub fn solve(&mut self, game: &mut GameState, moves: u32) -> MateSolution {
+        let mut keys = Vec::new();
+        for (move_, _) in ordered_moves(game) {
+            game.make_move(move_);
+            let length = (1..=moves).find(|&n| self.defend(game, n - 1));
+            game.pop_move();
+            if let Some(length) = length {
+                keys.push(self.build_line(game, move_, length));
+            }
+        }
+        keys.sort_by_key(|line| line.length);
+        MateSolution { keys }
+    }
+
+    fn attack(&mut self, game: &mut GameState, moves: u32) -> bool {
+        if moves == 0 {
+            return false;
+        }
+        let hash = game.get_hash();
+        let (mates, fails) = self.bounds.get(&hash).copied().unwrap_or((u32::MAX, 0));
+        if moves >= mates {
+            return true;
+        }
+        if moves <= fails {
+            return false;
+        }
+        let found = self.find_mate(game, moves).is_some();
+        let bounds = self.bounds.entry(hash).or_insert((u32::MAX, 0));
+        match found {
+            true => bounds.0 = bounds.0.min(moves),
+            false => bounds.1 = bounds.1.max(moves),
+        }
+        found
+    }
+
+    // the first move that mates in at most `moves`
+    fn find_mate(&mut self, game: &mut GameState, moves: u32) -> Option<Move> {
+        self.nodes += 1;
+        for (move_, check) in ordered_moves(game) {
+            // only a check can mate straight away, and the checks come first
+            if moves == 1 && !check {
+                break;
+            }
+            game.make_move(move_);
+            let mates = self.defend(game, moves - 1);
+            game.pop_move();
+            if mates {
+                return Some(move_);
+            }
+        }
+        None
+    }
+
+    // whether every reply to the last move is mated within `moves`
+    fn defend(&mut self, game: &mut GameState, moves: u32) -> bool {
+        self.nodes += 1;
+        let replies = game.legal_moves();
+        if replies.is_empty() {
+            return game.is_check();
+        }
+        replies.into_iter().all(|reply| {
+            game.make_move(reply);
+            let mated = self.attack(game, moves);
+            game.pop_move();
+            mated
+        })
+    }
+
+    // `move_` must mate in exactly `length`
+    fn build_line(&mut self, game: &mut GameState, move_: Move, length: u32) -> MateLine {
+        game.make_move(move_);
+        let mut defences = Vec::new();
+        for reply in game.legal_moves() {
+            game.make_move(reply);
+            let answer = (1..length).find_map(|n| self.find_mate(game, n).map(|m| (m, n)));
+            if let Some((answer, n)) = answer {
+                defences.push(Defence {
+                    move_: reply,
+                    answer: self.build_line(game, answer, n),
+                });
+            }
+            game.pop_move();
+        }
+        game.pop_move();
+        defences.sort_by_key(|defence| Reverse(defence.answer.length));
+        MateLine {
+            move_,
+            length,
+            defences,
+        }
+    }
+}
+
+// checks first, then captures, then everything else
+fn ordered_moves(game: &mut GameState) -> Vec<(Move, bool)> {
+    let mut moves: Vec<(Move, bool)> = game
+        .legal_moves()
+        .into_iter()
+        .map(|move_| {
+            game.make_move(move_);
+            let check = game.is_check();
+            game.pop_move();
+            (move_, check)
+        })
+        .collect();
+    moves.sort_by_key(|&(move_, check)| {
+        let capture = matches!(move_, Move::EnPassant { .. })
+            || game.board().get_square(move_.get_to()).is_some();
+        (!check, !capture)
+    });
+    moves
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // every defence is answered and every line ends in mate on time
+    fn check_line(game: &mut GameState, line: &MateLine) {
+        game.make_move(line.move_);
+        let replies = game.legal_moves();
+        match line.length {
+            1 => assert!(replies.is_empty() && game.is_check()),
+            _ => {
+                assert_eq!(line.defences.len(), replies.len());
+                for defence in &line.defences {
+                    assert!(replies.contains(&defence.move_));
+                    assert!(defence.answer.length < line.length);
+                    game.make_move(defence.move_);
+                    check_line(game, &defence.answer);
+                    game.pop_move();
+                }
+                assert!(line
+                    .defences
+                    .iter()
+                    .any(|d| d.answer.length == line.length - 1));
+            }
+        }
+        game.pop_move();
+    }
+
+    #[test]
+    fn test_mate_in_one() {
+        let mut game = GameState::try_from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        let mut solver = MateSolver::new();
+        let solution = solver.solve(&mut game, 1);
+        assert!(solution.is_unique());
+        let key = solution.get_key().unwrap();
+        assert_eq!(key.move_.to_string(), "a1a8");
+        assert!(key.defences.is_empty());
+        assert!(solution.get_cooks().is_empty());
+        check_line(&mut game, key);
+        // no mate in one for the other side, and none from the start
+        let mut game = GameState::try_from_fen("6k1/5ppp/8/8/8/8/8/R5K1 b - - 0 1").unwrap();
+        assert!(!solver.is_mate_in(&mut game, 1));
+        let mut game = GameState::default();
+        assert!(!solver.solve(&mut game, 1).is_mate());
+    }
+
+    #[test]
+    fn test_mate_in_two() {
+        // the rook can't mate at once while the black king has a7
+        let fen = "k7/8/2K5/8/8/8/8/7R w - - 0 1";
+        let mut game = GameState::try_from_fen(fen).unwrap();
+        let mut solver = MateSolver::new();
+        assert!(!solver.is_mate_in(&mut game, 1));
+        assert!(solver.is_mate_in(&mut game, 2));
+        let solution = solver.solve(&mut game, 2);
+        assert!(solution.is_mate());
+        for key in &solution.keys {
+            assert_eq!(key.length, 2);
+            check_line(&mut game, key);
+        }
+        assert_eq!(game.to_fen().as_ref(), fen);
+        assert!(solver.get_nodes() > 0);
+    }
+
+    #[test]
+    fn test_cooks() {
+        // either rook mates on the back rank
+        let mut game = GameState::try_from_fen("6k1/5ppp/8/8/8/8/8/RR4K1 w - - 0 1").unwrap();
+        let solution = MateSolver::new().solve(&mut game, 1);
+        assert!(!solution.is_unique());
+        assert_eq!(solution.get_cooks().len(), 1);
+        let mut keys: Vec<_> = solution.keys.iter().map(|k| k.move_.to_string()).collect();
+        keys.sort();
+        assert_eq!(keys, ["a1a8", "b1b8"]);
+        // a shorter mate counts as a solution of the longer stipulation
+        let solution = MateSolver::new().solve(&mut game, 2);
+        assert!(solution.keys.len() >= 2);
+        assert!(solution.keys[..2].iter().all(|key| key.length == 1));
+    }
+
+    #[test]
+    fn test_stalemate_is_not_mate() {
+        // Qb6 leaves black without a move but not in check
+        let mut game = GameState::try_from_fen("k7/2K5/8/8/8/8/8/1Q6 w - - 0 1").unwrap();
+        let solution = MateSolver::new().solve(&mut game, 1);
+        let mut keys: Vec<_> = solution.keys.iter().map(|k| k.move_.to_string()).collect();
+        keys.sort();
+        assert_eq!(keys, ["b1a1", "b1a2", "b1b7", "b1b8"]);
+        game.make_move(game.parse_move("b1b6").unwrap());
+        assert!(game.legal_moves().is_empty() && !game.is_check());
+    }
+}
@@ -0,0 +1,349 @@
+use crate::{
+    board::{Board, Square},
+    pieces::{Color, Figure, Piece},
+};
+use alloc::{sync::Arc, vec::Vec};
+use core::fmt;
+
+const INPUTS: usize = 768;
+const MAGIC: &[u8; 4] = b"RCNN";
+const VERSION: u16 = 1;
+const HEADER_BYTES: usize = 8;
+// quantisation of the hidden layer and the output weights
+const QA: i64 = 255;
+const QB: i64 = 64;
+const SCALE: i64 = 400;
+const MAX_EVAL: i64 = 20_000;
+// chunks summed in i32 lanes before widening, each adds at most 2 * QA * 2^15
+const AVX2_BLOCK: usize = 64;
+
+// a 768 -> N -> 1 network evaluated from both sides' points of view. The file
+// starts with the magic "RCNN", a u16 version and a u16 hidden size, followed
+// by little endian i16s: feature weights by input then neuron, feature biases,
+// output weights for the side to move then the other side, and the output bias.
+// Inputs are indexed by own/enemy, figure (pawn, knight, bishop, rook, queen,
+// king) and square, with the board flipped for black.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Network {
+    hidden: usize,
+    feature_weights: Vec<i16>,
+    feature_bias: Vec<i16>,
+    output_weights: Vec<i16>,
+    output_bias: i16,
+}
+
+impl Network {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, &'static str> {
+        if bytes.len() < HEADER_BYTES || &bytes[..4] != MAGIC {
+            return Err("Not a network file");
+        }
+        if u16::from_le_bytes([bytes[4], bytes[5]]) != VERSION {
+            return Err("Unsupported network version");
+        }
+        let hidden = u16::from_le_bytes([bytes[6], bytes[7]]) as usize;
+        // weights and biases for each neuron plus one output bias
+        let bytes = &bytes[HEADER_BYTES..];
+        if hidden == 0 || bytes.len() != 2 * (hidden * (INPUTS + 1 + 2) + 1) {
+            return Err("Invalid network size");
+        }
+        let values: Vec<i16> = bytes
+            .chunks_exact(2)
+            .map(|pair| i16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+        let (feature_weights, rest) = values.split_at(INPUTS * hidden);
+        let (feature_bias, rest) = rest.split_at(hidden);
+        let (output_weights, rest) = rest.split_at(2 * hidden);
+        Ok(Self {
+            hidden,
+            feature_weights: feature_weights.to_vec(),
+            feature_bias: feature_bias.to_vec(),
+            output_weights: output_weights.to_vec(),
+            output_bias: rest[0],
+        })
+    }
+
+    #[cfg(feature = "std")]
+    pub fn load(path: impl AsRef<std::path::Path>) -> Result<Self, &'static str> {
+        let bytes = std::fs::read(path).map_err(|_| "Could not read network file")?;
+        Self::from_bytes(&bytes)
+    }
+
+    pub fn get_hidden_size(&self) -> usize {
+        self.hidden
+    }
+
+    fn get_weights(&self, feature: usize) -> &[i16] {
+        &self.feature_weights[feature * self.hidden..(feature + 1) * self.hidden]
+    }
+}
+
+impl fmt::Debug for Network {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Network(768 -> {} -> 1)", self.hidden)
+    }
+}
+
+// first layer outputs for white's and black's point of view, kept up to date
+// by the board as pieces come and go
+#[derive(Debug, Clone)]
+pub struct Accumulator {
+    network: Arc<Network>,
+    values: [Vec<i16>; 2],
+}
+
+impl Accumulator {
+    pub fn new(network: Arc<Network>, board: &Board) -> Self {
+        let mut accumulator = Self {
+            values: [network.feature_bias.clone(), network.feature_bias.clone()],
+            network,
+        };
+        for square in board.get_occupied() {
+            if let Some(piece) = board.get_square(square) {
+                accumulator.add(piece, square);
+            }
+        }
+        accumulator
+    }
+
+    pub fn get_network(&self) -> &Arc<Network> {
+        &self.network
+    }
+
+    pub fn get_values(&self, perspective: Color) -> &[i16] {
+        &self.values[perspective as usize]
+    }
+
+    // wrapping, so a network with large weights can't panic and removing a
+    // piece still exactly undoes adding it
+    pub fn add(&mut self, piece: Piece, square: Square) {
+        for perspective in [Color::White, Color::Black] {
+            let weights = self
+                .network
+                .get_weights(feature_index(perspective, piece, square));
+            let values = &mut self.values[perspective as usize];
+            values
+                .iter_mut()
+                .zip(weights)
+                .for_each(|(v, w)| *v = v.wrapping_add(*w));
+        }
+    }
+
+    pub fn remove(&mut self, piece: Piece, square: Square) {
+        for perspective in [Color::White, Color::Black] {
+            let weights = self
+                .network
+                .get_weights(feature_index(perspective, piece, square));
+            let values = &mut self.values[perspective as usize];
+            values
+                .iter_mut()
+                .zip(weights)
+                .for_each(|(v, w)| *v = v.wrapping_sub(*w));
+        }
+    }
+
+    // centipawns from the side to move's point of view
+    pub fn evaluate(&self, turn: Color) -> i32 {
+        let network = &self.network;
+        let (us, them) = network.output_weights.split_at(network.hidden);
+        let sum = crelu_dot(&self.values[turn as usize], us)
+            + crelu_dot(&self.values[!turn as usize], them)
+            + network.output_bias as i64;
+        (sum * SCALE / (QA * QB)).clamp(-MAX_EVAL, MAX_EVAL) as i32
+    }
+}
+
+// the network is shared, so two accumulators are equal when they were built
+// from the same one
+impl PartialEq for Accumulator {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.network, &other.network) && self.values == other.values
+    }
+}
+
+impl Eq for Accumulator {}
+
+fn feature_index(perspective: Color, piece: Piece, square: Square) -> usize {
+    let figure = match piece.figure {
+        Figure::Pawn => 0,
+        Figure::Knight => 1,
+        Figure::Bishop => 2,
+        Figure::Rook => 3,
+        Figure::Queen => 4,
+        Figure::King => 5,
+    };
+    let (side, square) = match perspective {
+        Color::White => (piece.color as usize, usize::from(square)),
+        Color::Black => (!piece.color as usize, usize::from(square) ^ 56),
+    };
+    384 * side + 64 * figure + square
+}
+
+fn crelu_dot(values: &[i16], weights: &[i16]) -> i64 {
+    #[cfg(all(feature = "std", target_arch = "x86_64"))]
+    if std::is_x86_feature_detected!("avx2") {
+        // SAFETY: avx2 support was just checked
+        return unsafe { crelu_dot_avx2(values, weights) };
+    }
+    crelu_dot_scalar(values, weights)
+}
+
+fn crelu_dot_scalar(values: &[i16], weights: &[i16]) -> i64 {
+    values
+        .iter()
+        .zip(weights)
+        .map(|(&v, &w)| (v as i64).clamp(0, QA) * w as i64)
+        .sum()
+}
+
+#[cfg(all(feature = "std", target_arch = "x86_64"))]
+#[target_feature(enable = "avx2")]
+unsafe fn crelu_dot_avx2(values: &[i16], weights: &[i16]) -> i64 {
+    use core::arch::x86_64::*;
+    const LANES: usize = 16;
+    let len = values.len().min(weights.len());
+    let chunks = len / LANES;
+    let zero = _mm256_setzero_si256();
+    let max = _mm256_set1_epi16(QA as i16);
+    let mut total = 0;
+    for block in (0..chunks).step_by(AVX2_BLOCK) {
+        let mut sum = _mm256_setzero_si256();
+        for chunk in block..chunks.min(block + AVX2_BLOCK) {
+            let v = _mm256_loadu_si256(values.as_ptr().add(chunk * LANES) as *const __m256i);
+            let w = _mm256_loadu_si256(weights.as_ptr().add(chunk * LANES) as *const __m256i);
+            let clipped = _mm256_min_epi16(_mm256_max_epi16(v, zero), max);
+            sum = _mm256_add_epi32(sum, _mm256_madd_epi16(clipped, w));
+        }
+        let mut lanes = [0i32; 8];
+        _mm256_storeu_si256(lanes.as_mut_ptr() as *mut __m256i, sum);
+        total += lanes.iter().map(|&lane| lane as i64).sum::<i64>();
+    }
+    let tail = chunks * LANES;
+    total + crelu_dot_scalar(&values[tail..len], &weights[tail..len])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gamestate::GameState;
+
+    static TEST_NETWORK: &[u8] = include_bytes!("../nets/test-16.nnue");
+
+    fn test_network() -> Arc<Network> {
+        Arc::new(Network::from_bytes(TEST_NETWORK).unwrap())
+    }
+
+    #[test]
+    fn test_load_network() {
+        let network = test_network();
+        assert_eq!(network.get_hidden_size(), 16);
+        assert!(Network::from_bytes(&TEST_NETWORK[1..]).is_err());
+        assert!(Network::from_bytes(&TEST_NETWORK[2..]).is_err());
+        assert!(Network::from_bytes(&[]).is_err());
+        // a headerless network of the right length is still refused
+        assert!(Network::from_bytes(&TEST_NETWORK[HEADER_BYTES..]).is_err());
+        let mut bytes = TEST_NETWORK.to_vec();
+        bytes[4] += 1;
+        assert_eq!(
+            Network::from_bytes(&bytes),
+            Err("Unsupported network version")
+        );
+        bytes[..4].copy_from_slice(b"NNUE");
+        assert_eq!(Network::from_bytes(&bytes), Err("Not a network file"));
+    }
+
+    #[test]
+    fn test_incremental_matches_refresh() {
+        fn walk(game: &mut GameState, depth: u32) {
+            let accumulator = game.board().get_accumulator().unwrap();
+            let network = Arc::clone(accumulator.get_network());
+            assert_eq!(accumulator, &Accumulator::new(network, game.board()));
+            if depth == 0 {
+                return;
+            }
+            for move_ in game.legal_moves() {
+                game.make_move(move_);
+                walk(game, depth - 1);
+                game.pop_move();
+            }
+        }
+        let network = test_network();
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        ] {
+            let mut game = GameState::try_from_fen(fen).unwrap();
+            game.set_network(Some(Arc::clone(&network)));
+            let start = game.board().clone();
+            walk(&mut game, 2);
+            assert_eq!(game.board(), &start);
+        }
+    }
+
+    #[test]
+    fn test_perspectives_are_symmetric() {
+        let network = test_network();
+        // the same position with colours swapped and the board flipped
+        for (fen, mirrored) in [
+            (
+                "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+                "r3k2r/pppbbppp/2n2q1P/1P2p3/3pn3/BN2PNP1/P1PPQPB1/R3K2R b KQkq - 0 1",
+            ),
+            (
+                "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1",
+                "4k3/4p3/8/8/8/8/8/4K3 b - - 0 1",
+            ),
+        ] {
+            let mut game = GameState::try_from_fen(fen).unwrap();
+            let mut other = GameState::try_from_fen(mirrored).unwrap();
+            game.set_network(Some(Arc::clone(&network)));
+            other.set_network(Some(Arc::clone(&network)));
+            let evaluate = |game: &GameState| {
+                game.board()
+                    .get_accumulator()
+                    .unwrap()
+                    .evaluate(game.turn())
+            };
+            assert_eq!(evaluate(&game), evaluate(&other), "{fen}");
+        }
+    }
+
+    #[test]
+    fn test_simd_matches_scalar() {
+        let values: Vec<i16> = (0..37).map(|i| (i * 37 % 600 - 150) as i16).collect();
+        let weights: Vec<i16> = (0..37).map(|i| (i * 11 % 200 - 100) as i16).collect();
+        let expected: i64 = values
+            .iter()
+            .zip(&weights)
+            .map(|(&v, &w)| (v as i64).clamp(0, QA) * w as i64)
+            .sum();
+        assert_eq!(crelu_dot_scalar(&values, &weights), expected);
+        assert_eq!(crelu_dot(&values, &weights), expected);
+        // enough saturated products to overflow an i32
+        let values = vec![i16::MAX; 4000];
+        let weights = vec![i16::MAX; 4000];
+        let expected = 4000 * QA * i16::MAX as i64;
+        assert_eq!(crelu_dot(&values, &weights), expected);
+    }
+
+    #[test]
+    fn test_extreme_weights() {
+        // every weight and bias at the limit, which would overflow both the
+        // accumulator and an i32 output sum
+        let hidden = 256;
+        let mut bytes = TEST_NETWORK[..HEADER_BYTES].to_vec();
+        bytes[6..8].copy_from_slice(&(hidden as u16).to_le_bytes());
+        for _ in 0..hidden * (INPUTS + 1 + 2) + 1 {
+            bytes.extend(i16::MAX.to_le_bytes());
+        }
+        let network = Arc::new(Network::from_bytes(&bytes).unwrap());
+        let mut game = GameState::default();
+        game.set_network(Some(Arc::clone(&network)));
+        for move_ in ["e2e4", "d7d5", "e4d5"] {
+            game.make_move(game.parse_move(move_).unwrap());
+        }
+        let accumulator = game.board().get_accumulator().unwrap();
+        assert_eq!(accumulator, &Accumulator::new(network, game.board()));
+        assert!(accumulator.evaluate(game.turn()).abs() <= MAX_EVAL as i32);
+    }
+}
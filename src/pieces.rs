@@ -1,4 +1,4 @@
-use std::ops::Not;
+use core::{fmt, ops::Not, str::FromStr};
 
 use self::constants::*;
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -63,6 +63,69 @@ impl TryFrom<char> for Piece {
     }
 }
 
+impl From<Piece> for u8 {
+    fn from(piece: Piece) -> Self {
+        6 * piece.color as u8 + piece.figure as u8
+    }
+}
+
+impl TryFrom<u8> for Piece {
+    type Error = &'static str;
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        let color = match value / 6 {
+            0 => Color::White,
+            1 => Color::Black,
+            _ => return Err("Invalid piece code"),
+        };
+        let figure = match value % 6 {
+            0 => Figure::Pawn,
+            1 => Figure::Rook,
+            2 => Figure::Knight,
+            3 => Figure::Bishop,
+            4 => Figure::Queen,
+            _ => Figure::King,
+        };
+        Ok(Piece { color, figure })
+    }
+}
+
+impl fmt::Display for Piece {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", char::from(*self))
+    }
+}
+
+impl FromStr for Piece {
+    type Err = &'static str;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Self::try_from(c),
+            _ => Err("Invalid piece"),
+        }
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::White => write!(f, "w"),
+            Self::Black => write!(f, "b"),
+        }
+    }
+}
+
+impl FromStr for Color {
+    type Err = &'static str;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "w" => Ok(Self::White),
+            "b" => Ok(Self::Black),
+            _ => Err("Invalid color"),
+        }
+    }
+}
+
 impl Not for Color {
     type Output = Self;
     fn not(self) -> Self::Output {
@@ -0,0 +1,1257 @@
+use crate::{
+    eval::evaluate,
+    gamestate::{GameState, GenMode, Move},
+    pieces::{Color, Figure, Piece},
+};
+use alloc::{sync::Arc, vec::Vec};
+use core::cmp::Reverse;
+use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::{
+    sync::LazyLock,
+    thread,
+    time::{Duration, Instant},
+};
+
+mod movepick;
+mod searcher;
+mod skill;
+mod time;
+mod tt;
+pub use movepick::is_tactical;
+use movepick::{Heuristics, MovePicker};
+pub use searcher::{SearchHandle, Searcher};
+pub use skill::{Skill, MAX_ELO, MAX_SKILL_LEVEL, MIN_ELO};
+pub use time::{Clock, TimeManager, DEFAULT_MOVE_OVERHEAD};
+pub use tt::{Bound, TTEntry, TranspositionTable, DEFAULT_HASH_MB};
+
+pub const MATE_SCORE: i32 = 30_000;
+pub const MAX_PLY: u32 = 128;
+const INFINITY: i32 = 32_000;
+const DRAW_SCORE: i32 = 0;
+const DELTA_MARGIN: i32 = 200;
+const CHECK_INTERVAL: u64 = 1024;
+const ASPIRATION_DEPTH: u32 = 4;
+const ASPIRATION_WINDOW: i32 = 25;
+const REVERSE_FUTILITY_DEPTH: u32 = 6;
+const REVERSE_FUTILITY_MARGIN: i32 = 80;
+const RAZORING_DEPTH: u32 = 2;
+const RAZORING_MARGIN: i32 = 250;
+const FUTILITY_DEPTH: u32 = 3;
+const FUTILITY_MARGIN: i32 = 120;
+const SINGULAR_DEPTH: u32 = 8;
+
+// search selectivity, each can be switched off to measure what it is worth
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SearchOptions {
+    pub null_move: bool,
+    pub late_move_reductions: bool,
+    pub reverse_futility: bool,
+    pub futility: bool,
+    pub razoring: bool,
+    pub check_extensions: bool,
+    pub singular_extensions: bool,
+    pub aspiration_windows: bool,
+}
+
+impl SearchOptions {
+    pub const NONE: Self = Self {
+        null_move: false,
+        late_move_reductions: false,
+        reverse_futility: false,
+        futility: false,
+        razoring: false,
+        check_extensions: false,
+        singular_extensions: false,
+        aspiration_windows: false,
+    };
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            null_move: true,
+            late_move_reductions: true,
+            reverse_futility: true,
+            futility: true,
+            razoring: true,
+            check_extensions: true,
+            singular_extensions: true,
+            aspiration_windows: true,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct SearchLimits {
+    pub depth: Option<u32>,
+    pub nodes: Option<u64>,
+    pub movetime: Option<Duration>,
+    pub clock: Option<Clock>,
+    // subtracted from every time limit for communication delays
+    pub move_overhead: Duration,
+    // stop once a mate in this many moves is found
+    pub mate: Option<u32>,
+    // only these root moves are searched when not empty
+    pub searchmoves: Vec<Move>,
+    // number of best root moves to search lines for, 0 is the same as 1
+    pub multipv: usize,
+    // plays weaker moves on purpose, see Skill
+    pub skill: Option<Skill>,
+    // while the flag is set the search thinks on the opponent's time and no
+    // time limit applies. Clearing it is a ponderhit: the clock starts and
+    // the search carries on as if it had been started normally.
+    pub ponder: Option<Arc<AtomicBool>>,
+    pub infinite: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchInfo {
+    pub depth: u32,
+    pub seldepth: u32,
+    // rank of the line, starting at 1
+    pub multipv: usize,
+    pub score: i32,
+    pub nodes: u64,
+    pub nps: u64,
+    pub time: Duration,
+    pub hashfull: u32,
+    pub pv: Vec<Move>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchResult {
+    pub best_move: Option<Move>,
+    pub score: i32,
+    pub depth: u32,
+    pub nodes: u64,
+    pub pv: Vec<Move>,
+    // best first, as many as multipv asked for and there were root moves
+    pub lines: Vec<PvLine>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PvLine {
+    pub score: i32,
+    pub pv: Vec<Move>,
+}
+
+struct SearchContext<'a> {
+    tt: &'a TranspositionTable,
+    heuristics: Heuristics,
+    limits: &'a SearchLimits,
+    options: SearchOptions,
+    stop: &'a AtomicBool,
+    time: TimeManager,
+    start: Instant,
+    // when the clock started, which is later than start after a ponderhit
+    clock_start: Instant,
+    pondering: bool,
+    nodes: u64,
+    // nodes of every thread, each adds its own every CHECK_INTERVAL nodes
+    shared_nodes: &'a AtomicU64,
+    flushed_nodes: u64,
+    // nodes spent below each root move in the current iteration
+    root_nodes: Vec<(Move, u64)>,
+    stopped: bool,
+    root_depth: u32,
+    // deepest ply reached in the current iteration
+    seldepth: u32,
+    // per ply: the move left out by a singular search, and whether the
+    // previous move was a null move
+    excluded: Vec<Option<Move>>,
+    null_move: Vec<bool>,
+    // root moves of the lines already searched in this iteration
+    root_excluded: Vec<Move>,
+}
+
+impl<'a> SearchContext<'a> {
+    fn new(
+        tt: &'a TranspositionTable,
+        limits: &'a SearchLimits,
+        options: SearchOptions,
+        stop: &'a AtomicBool,
+        shared_nodes: &'a AtomicU64,
+    ) -> Self {
+        Self {
+            tt,
+            heuristics: Heuristics::new(),
+            limits,
+            options,
+            stop,
+            time: TimeManager::new(limits),
+            start: Instant::now(),
+            clock_start: Instant::now(),
+            pondering: limits.ponder.is_some(),
+            nodes: 0,
+            shared_nodes,
+            flushed_nodes: 0,
+            root_nodes: Vec::new(),
+            stopped: false,
+            root_depth: 0,
+            seldepth: 0,
+            excluded: vec![None; MAX_PLY as usize + 1],
+            null_move: vec![false; MAX_PLY as usize + 1],
+            root_excluded: Vec::new(),
+        }
+    }
+
+    fn should_stop(&mut self) -> bool {
+        if self.stopped {
+            return true;
+        }
+        if self.nodes.is_multiple_of(CHECK_INTERVAL) {
+            self.flush_nodes();
+            self.stopped = self.stop.load(Ordering::Relaxed)
+                || self
+                    .limits
+                    .nodes
+                    .is_some_and(|n| self.get_total_nodes() >= n)
+                || self
+                    .get_clock_time()
+                    .is_some_and(|elapsed| self.time.is_hard_limit_reached(elapsed));
+        }
+        self.stopped
+    }
+
+    // time used on our own clock, None while pondering
+    fn get_clock_time(&mut self) -> Option<Duration> {
+        if self.pondering {
+            let ponder = self.limits.ponder.as_ref();
+            if ponder.is_some_and(|ponder| ponder.load(Ordering::Relaxed)) {
+                return None;
+            }
+            self.pondering = false;
+            self.clock_start = Instant::now();
+        }
+        Some(self.clock_start.elapsed())
+    }
+
+    fn flush_nodes(&mut self) {
+        self.shared_nodes
+            .fetch_add(self.nodes - self.flushed_nodes, Ordering::Relaxed);
+        self.flushed_nodes = self.nodes;
+    }
+
+    fn get_total_nodes(&self) -> u64 {
+        self.shared_nodes.load(Ordering::Relaxed) + self.nodes - self.flushed_nodes
+    }
+}
+
+pub fn is_mate_score(score: i32) -> bool {
+    score.abs() >= MATE_SCORE - MAX_PLY as i32
+}
+
+pub fn search(
+    game: &mut GameState,
+    tt: &TranspositionTable,
+    limits: &SearchLimits,
+    options: &SearchOptions,
+    stop: &AtomicBool,
+    on_info: impl FnMut(&SearchInfo),
+) -> SearchResult {
+    search_threads(game, tt, limits, options, 1, stop, on_info)
+}
+
+// the quiescence search on its own, for tools such as the tuner that want the
+// quiet position at the end of the capture sequence
+pub fn quiesce_pv(game: &mut GameState) -> (i32, Vec<Move>) {
+    // quiescence never probes the table, and nothing ever sets the flag
+    static TT: LazyLock<TranspositionTable> = LazyLock::new(|| TranspositionTable::new(1));
+    static STOP: AtomicBool = AtomicBool::new(false);
+    let limits = SearchLimits::default();
+    let nodes = AtomicU64::new(0);
+    let mut ctx = SearchContext::new(&TT, &limits, SearchOptions::default(), &STOP, &nodes);
+    let mut pv = Vec::new();
+    let score = ctx.quiescence(game, -INFINITY, INFINITY, 0, &mut pv);
+    (score, pv)
+}
+
+// lazy smp: helper threads search copies of the position and only share the
+// transposition table. This thread keeps the clock and reports progress, and
+// the helpers stop as soon as it is done.
+pub fn search_threads(
+    game: &mut GameState,
+    tt: &TranspositionTable,
+    limits: &SearchLimits,
+    options: &SearchOptions,
+    threads: usize,
+    stop: &AtomicBool,
+    on_info: impl FnMut(&SearchInfo),
+) -> SearchResult {
+    // a weakened search looks at a few lines to a capped depth and then
+    // picks one of them
+    let skill = limits.skill.filter(Skill::is_enabled);
+    let skill_limits;
+    let limits = match skill {
+        Some(skill) => {
+            skill_limits = SearchLimits {
+                depth: Some(
+                    limits
+                        .depth
+                        .map_or(skill.get_depth(), |d| d.min(skill.get_depth())),
+                ),
+                multipv: limits.multipv.max(skill.get_multipv()),
+                ..limits.clone()
+            };
+            &skill_limits
+        }
+        None => limits,
+    };
+    tt.new_search();
+    let nodes = AtomicU64::new(0);
+    let helper_stop = AtomicBool::new(false);
+    let helper_limits = SearchLimits {
+        depth: limits.depth,
+        searchmoves: limits.searchmoves.clone(),
+        multipv: limits.multipv,
+        ..SearchLimits::default()
+    };
+    thread::scope(|scope| {
+        let helpers: Vec<_> = (1..threads.max(1))
+            .map(|id| {
+                let mut game = game.clone();
+                let (limits, stop, nodes) = (&helper_limits, &helper_stop, &nodes);
+                scope.spawn(move || {
+                    let mut ctx = SearchContext::new(tt, limits, *options, stop, nodes);
+                    // half the helpers run a ply ahead to spread out the depths
+                    ctx.iterate(&mut game, 1 + id as u32 % 2, |_| ())
+                })
+            })
+            .collect();
+        let mut ctx = SearchContext::new(tt, limits, *options, stop, &nodes);
+        let mut result = ctx.iterate(game, 1, on_info);
+        helper_stop.store(true, Ordering::Relaxed);
+        // a helper that completed a deeper iteration knows more
+        for helper in helpers {
+            let helper = helper.join().unwrap();
+            if helper.best_move.is_some()
+                && (helper.depth, helper.score) > (result.depth, result.score)
+            {
+                result = helper;
+            }
+        }
+        result.nodes = nodes.load(Ordering::Relaxed);
+        if let Some(line) = skill.and_then(|skill| skill.pick_line(&result.lines)) {
+            result.best_move = line.pv.first().copied();
+            result.score = line.score;
+            result.pv = line.pv.clone();
+        }
+        result
+    })
+}
+
+impl SearchContext<'_> {
+    fn iterate(
+        &mut self,
+        game: &mut GameState,
+        start_depth: u32,
+        mut on_info: impl FnMut(&SearchInfo),
+    ) -> SearchResult {
+        let root_moves = game
+            .legal_moves()
+            .into_iter()
+            .filter(|&move_| self.is_root_move(move_))
+            .count();
+        let multipv = self.limits.multipv.clamp(1, root_moves.max(1));
+        let mut result = SearchResult {
+            best_move: game
+                .legal_moves()
+                .into_iter()
+                .find(|&move_| self.is_root_move(move_)),
+            score: 0,
+            depth: 0,
+            nodes: 0,
+            pv: Vec::new(),
+            lines: Vec::new(),
+        };
+        let max_depth = self.limits.depth.unwrap_or(MAX_PLY).min(MAX_PLY);
+        for depth in start_depth.min(max_depth)..=max_depth {
+            if self.stop.load(Ordering::Relaxed) {
+                break;
+            }
+            self.root_depth = depth;
+            self.root_nodes.clear();
+            self.seldepth = 0;
+            let iteration_start = self.nodes;
+            // each line leaves out the root moves of the ones before it
+            let mut lines = Vec::with_capacity(multipv);
+            for index in 0..multipv {
+                let prev_score = result.lines.get(index).map_or(result.score, |l| l.score);
+                let mut pv = Vec::new();
+                let score = self.aspiration_search(game, depth, prev_score, &mut pv);
+                if self.stopped || pv.is_empty() {
+                    break;
+                }
+                self.root_excluded.push(pv[0]);
+                lines.push(PvLine { score, pv });
+            }
+            self.root_excluded.clear();
+            // a partially searched iteration can't be trusted
+            if lines.len() < multipv {
+                break;
+            }
+            // equal scores keep the order of the previous iteration so that
+            // tied moves don't swap places from one depth to the next
+            lines.sort_by_key(|line| {
+                let prev_rank = result.lines.iter().position(|l| l.pv[0] == line.pv[0]);
+                (Reverse(line.score), prev_rank.unwrap_or(usize::MAX))
+            });
+            let score = lines[0].score;
+            result.best_move = lines[0].pv.first().copied();
+            result.score = score;
+            result.depth = depth;
+            result.pv.clone_from(&lines[0].pv);
+            let (nodes, time) = (self.get_total_nodes(), self.start.elapsed());
+            for (index, line) in lines.iter().enumerate() {
+                on_info(&SearchInfo {
+                    depth,
+                    seldepth: self.seldepth.max(depth),
+                    multipv: index + 1,
+                    score: line.score,
+                    nodes,
+                    nps: (nodes as u128 * 1000 / time.as_millis().max(1)) as u64,
+                    time,
+                    hashfull: self.tt.hashfull(),
+                    pv: line.pv.clone(),
+                });
+            }
+            result.lines = lines;
+            // deeper iterations can't find a shorter mate
+            let mate_plies = (MATE_SCORE - score.abs()) as u32;
+            if is_mate_score(score) && mate_plies <= depth {
+                break;
+            }
+            if score > 0
+                && is_mate_score(score)
+                && self.limits.mate.is_some_and(|n| mate_plies < 2 * n)
+            {
+                break;
+            }
+            if let Some(best_move) = result.best_move {
+                let best_move_nodes = self.get_root_nodes(best_move);
+                let nodes = self.nodes - iteration_start;
+                self.time.update(best_move, score, best_move_nodes, nodes);
+            }
+            let elapsed = self.get_clock_time();
+            if elapsed.is_some_and(|elapsed| self.time.is_soft_limit_reached(elapsed)) {
+                break;
+            }
+        }
+        self.flush_nodes();
+        result.nodes = self.nodes;
+        result
+    }
+
+    fn is_root_move(&self, move_: Move) -> bool {
+        (self.limits.searchmoves.is_empty() || self.limits.searchmoves.contains(&move_))
+            && !self.root_excluded.contains(&move_)
+    }
+
+    fn get_root_nodes(&self, move_: Move) -> u64 {
+        self.root_nodes
+            .iter()
+            .find(|&&(m, _)| m == move_)
+            .map_or(0, |&(_, nodes)| nodes)
+    }
+
+    fn add_root_nodes(&mut self, move_: Move, nodes: u64) {
+        match self.root_nodes.iter_mut().find(|(m, _)| *m == move_) {
+            Some((_, total)) => *total += nodes,
+            None => self.root_nodes.push((move_, nodes)),
+        }
+    }
+
+    // start with a narrow window around the previous score and widen it on
+    // each failure
+    fn aspiration_search(
+        &mut self,
+        game: &mut GameState,
+        depth: u32,
+        prev_score: i32,
+        pv: &mut Vec<Move>,
+    ) -> i32 {
+        if !self.options.aspiration_windows || depth < ASPIRATION_DEPTH || is_mate_score(prev_score)
+        {
+            return self.negamax(game, depth, -INFINITY, INFINITY, 0, pv);
+        }
+        let mut delta = ASPIRATION_WINDOW;
+        let mut alpha = prev_score - delta;
+        let mut beta = prev_score + delta;
+        loop {
+            let score = self.negamax(game, depth, alpha, beta, 0, pv);
+            if self.stopped {
+                return score;
+            }
+            if score <= alpha {
+                alpha = (score - delta).max(-INFINITY);
+            } else if score >= beta {
+                beta = (score + delta).min(INFINITY);
+            } else {
+                return score;
+            }
+            delta *= 2;
+        }
+    }
+
+    fn negamax(
+        &mut self,
+        game: &mut GameState,
+        mut depth: u32,
+        mut alpha: i32,
+        mut beta: i32,
+        ply: u32,
+        pv: &mut Vec<Move>,
+    ) -> i32 {
+        pv.clear();
+        self.nodes += 1;
+        self.seldepth = self.seldepth.max(ply);
+        if self.should_stop() {
+            return 0;
+        }
+        if ply > 0 {
+            if game.is_fifty_move_draw()
+                || game.is_insufficient_material()
+                || game.repetitions() > 0
+            {
+                return DRAW_SCORE;
+            }
+            // no line from here can beat a mate already found closer to the root
+            alpha = alpha.max(-MATE_SCORE + ply as i32);
+            beta = beta.min(MATE_SCORE - ply as i32 - 1);
+            if alpha >= beta {
+                return alpha;
+            }
+        }
+        let in_check = game.is_check();
+        if in_check && self.options.check_extensions && ply < 2 * self.root_depth {
+            depth += 1;
+        }
+        if depth == 0 || ply >= MAX_PLY {
+            return self.quiescence(game, alpha, beta, ply, pv);
+        }
+
+        let excluded = self.excluded[ply as usize].take();
+        let hash = game.get_hash();
+        let pv_node = beta - alpha > 1;
+        // the singular verification search must not see or overwrite the
+        // entry of the full search at this node
+        let tt_entry = match excluded {
+            Some(_) => None,
+            None => self.tt.probe(hash, ply),
+        };
+        if let Some(entry) = tt_entry.filter(|e| !pv_node && e.depth as u32 >= depth) {
+            match entry.bound {
+                Bound::Exact => return entry.score,
+                Bound::Lower if entry.score >= beta => return entry.score,
+                Bound::Upper if entry.score <= alpha => return entry.score,
+                _ => (),
+            }
+        }
+        let tt_move = tt_entry.and_then(|e| game.move_from_u16(e.move16));
+        let static_eval = match in_check {
+            true => -INFINITY,
+            false => tt_entry.map_or_else(|| evaluate(game), |e| e.eval),
+        };
+
+        if !pv_node && !in_check && excluded.is_none() {
+            if let Some(score) = self.prune_node(game, depth, alpha, beta, ply, static_eval) {
+                return score;
+            }
+        }
+
+        let singular_extension = self.is_singular(game, depth, ply, tt_entry, tt_move, excluded);
+        self.heuristics.clear_killers(ply + 1);
+        let mut picker = MovePicker::new(game, &self.heuristics, tt_move, ply);
+        let mut move_count = 0;
+        let mut failed_quiets = Vec::new();
+        let mut best_move = None;
+        let mut child_pv = Vec::new();
+        while let Some(move_) = picker.next(game, &self.heuristics) {
+            if Some(move_) == excluded || (ply == 0 && !self.is_root_move(move_)) {
+                continue;
+            }
+            let quiet = !is_tactical(game, move_);
+            move_count += 1;
+            let nodes_before = self.nodes;
+            game.make_move(move_);
+            let gives_check = game.is_check();
+
+            // forward futility: quiet moves can't raise a hopeless static eval
+            if self.options.futility
+                && !pv_node
+                && !in_check
+                && !gives_check
+                && quiet
+                && move_count > 1
+                && depth <= FUTILITY_DEPTH
+                && static_eval + FUTILITY_MARGIN * depth as i32 <= alpha
+            {
+                game.pop_move();
+                continue;
+            }
+
+            let extension = match Some(move_) == tt_move {
+                true => singular_extension,
+                false => 0,
+            };
+            let new_depth = depth - 1 + extension;
+            let score = match move_count {
+                1 => -self.negamax(game, new_depth, -beta, -alpha, ply + 1, &mut child_pv),
+                _ => {
+                    // late quiet moves are searched shallower first
+                    let reduction = match self.options.late_move_reductions
+                        && depth >= 3
+                        && quiet
+                        && !in_check
+                        && !gives_check
+                    {
+                        true => lmr_reduction(depth, move_count, pv_node).min(new_depth - 1),
+                        false => 0,
+                    };
+                    let mut score = -self.negamax(
+                        game,
+                        new_depth - reduction,
+                        -alpha - 1,
+                        -alpha,
+                        ply + 1,
+                        &mut child_pv,
+                    );
+                    if reduction > 0 && score > alpha {
+                        score = -self.negamax(
+                            game,
+                            new_depth,
+                            -alpha - 1,
+                            -alpha,
+                            ply + 1,
+                            &mut child_pv,
+                        );
+                    }
+                    if score > alpha && score < beta {
+                        score =
+                            -self.negamax(game, new_depth, -beta, -alpha, ply + 1, &mut child_pv);
+                    }
+                    score
+                }
+            };
+            game.pop_move();
+            if ply == 0 {
+                self.add_root_nodes(move_, self.nodes - nodes_before);
+            }
+            if self.stopped {
+                return 0;
+            }
+            if score > alpha {
+                alpha = score;
+                best_move = Some(move_);
+                pv.clear();
+                pv.push(move_);
+                pv.extend_from_slice(&child_pv);
+                if alpha >= beta {
+                    if quiet {
+                        self.heuristics
+                            .update_quiet(game, ply, depth, move_, &failed_quiets);
+                    }
+                    break;
+                }
+            }
+            if quiet {
+                failed_quiets.push(move_);
+            }
+        }
+        if move_count == 0 {
+            return match (excluded, in_check) {
+                // only the excluded move was playable
+                (Some(_), _) => alpha,
+                (None, true) => -MATE_SCORE + ply as i32,
+                (None, false) => DRAW_SCORE,
+            };
+        }
+        // the root of a later multipv line is missing its best moves
+        if excluded.is_none() && (ply > 0 || self.root_excluded.is_empty()) {
+            let bound = match best_move {
+                _ if alpha >= beta => Bound::Lower,
+                Some(_) => Bound::Exact,
+                None => Bound::Upper,
+            };
+            let move16 = best_move.map_or(0, Move::to_u16);
+            let eval = match in_check {
+                true => 0,
+                false => static_eval,
+            };
+            self.tt.store(hash, ply, move16, alpha, eval, depth, bound);
+        }
+        alpha
+    }
+
+    // node level pruning before any move is searched, only at non-PV nodes
+    // that aren't in check
+    fn prune_node(
+        &mut self,
+        game: &mut GameState,
+        depth: u32,
+        alpha: i32,
+        beta: i32,
+        ply: u32,
+        static_eval: i32,
+    ) -> Option<i32> {
+        if is_mate_score(beta) {
+            return None;
+        }
+        // reverse futility: far enough above beta that a quiet move won't lose it
+        if self.options.reverse_futility
+            && depth <= REVERSE_FUTILITY_DEPTH
+            && static_eval - REVERSE_FUTILITY_MARGIN * depth as i32 >= beta
+        {
+            return Some(static_eval);
+        }
+        // razoring: so far below alpha that only captures could help
+        if self.options.razoring
+            && depth <= RAZORING_DEPTH
+            && static_eval + RAZORING_MARGIN * depth as i32 <= alpha
+        {
+            let score = self.quiescence(game, alpha, alpha + 1, ply, &mut Vec::new());
+            if score <= alpha {
+                return Some(score);
+            }
+        }
+        // null move: if passing still fails high a real move will too, unless
+        // the side to move is in zugzwang, which is common with only pawns left
+        if self.options.null_move
+            && depth >= 3
+            && static_eval >= beta
+            && has_non_pawn_material(game)
+            && !self.null_move[ply as usize]
+        {
+            let reduction = 3 + depth / 6 + ((static_eval - beta) / 200).clamp(0, 3) as u32;
+            let record = game.make_null_move();
+            self.null_move[ply as usize + 1] = true;
+            let mut child_pv = Vec::new();
+            let score = -self.negamax(
+                game,
+                depth.saturating_sub(reduction + 1),
+                -beta,
+                -beta + 1,
+                ply + 1,
+                &mut child_pv,
+            );
+            self.null_move[ply as usize + 1] = false;
+            game.unmake_null_move(record);
+            if self.stopped {
+                return Some(0);
+            }
+            if score >= beta {
+                // don't trust unproven mates found after passing
+                return Some(match is_mate_score(score) {
+                    true => beta,
+                    false => score,
+                });
+            }
+        }
+        None
+    }
+
+    // a hash move is singular if every alternative fails well below its score,
+    // in which case it is worth searching one ply deeper
+    fn is_singular(
+        &mut self,
+        game: &mut GameState,
+        depth: u32,
+        ply: u32,
+        tt_entry: Option<TTEntry>,
+        tt_move: Option<Move>,
+        excluded: Option<Move>,
+    ) -> u32 {
+        let (Some(entry), Some(tt_move)) = (tt_entry, tt_move) else {
+            return 0;
+        };
+        if !self.options.singular_extensions
+            || excluded.is_some()
+            || ply == 0
+            || depth < SINGULAR_DEPTH
+            || (entry.depth as u32) + 3 < depth
+            || entry.bound == Bound::Upper
+            || is_mate_score(entry.score)
+        {
+            return 0;
+        }
+        let singular_beta = entry.score - 2 * depth as i32;
+        self.excluded[ply as usize] = Some(tt_move);
+        let mut child_pv = Vec::new();
+        let score = self.negamax(
+            game,
+            (depth - 1) / 2,
+            singular_beta - 1,
+            singular_beta,
+            ply,
+            &mut child_pv,
+        );
+        self.excluded[ply as usize] = None;
+        match score < singular_beta {
+            true => 1,
+            false => 0,
+        }
+    }
+}
+
+fn lmr_reduction(depth: u32, move_count: u32, pv_node: bool) -> u32 {
+    let reduction = 0.75 + (depth as f64).ln() * (move_count as f64).ln() / 2.25;
+    (reduction as u32).saturating_sub(pv_node as u32)
+}
+
+fn has_non_pawn_material(game: &GameState) -> bool {
+    let color = game.turn();
+    [Figure::Knight, Figure::Bishop, Figure::Rook, Figure::Queen]
+        .into_iter()
+        .any(|figure| game.board().get_piece_count(Piece { color, figure }) > 0)
+}
+
+impl SearchContext<'_> {
+    fn quiescence(
+        &mut self,
+        game: &mut GameState,
+        mut alpha: i32,
+        beta: i32,
+        ply: u32,
+        pv: &mut Vec<Move>,
+    ) -> i32 {
+        pv.clear();
+        self.nodes += 1;
+        self.seldepth = self.seldepth.max(ply);
+        if self.should_stop() {
+            return 0;
+        }
+        if ply >= MAX_PLY {
+            return evaluate(game);
+        }
+        let in_check = game.is_check();
+        // the side to move can usually do at least as well as the static eval
+        // by declining every capture, except when it has to answer a check
+        let stand_pat = match in_check {
+            true => -INFINITY,
+            false => evaluate(game),
+        };
+        if stand_pat >= beta {
+            return beta;
+        }
+        alpha = alpha.max(stand_pat);
+
+        let mut picker = MovePicker::new_tactical(game, &self.heuristics);
+        let mut child_pv = Vec::new();
+        let mut move_count = 0;
+        while let Some(move_) = picker.next(game, &self.heuristics) {
+            move_count += 1;
+            if !in_check && move_.get_promotion().is_none() {
+                let gain = match move_ {
+                    Move::EnPassant { .. } => figure_value(Figure::Pawn),
+                    _ => game
+                        .board()
+                        .get_square(move_.get_to())
+                        .map_or(0, |p| figure_value(p.figure)),
+                };
+                if stand_pat + gain + DELTA_MARGIN < alpha {
+                    continue;
+                }
+            }
+            game.make_move(move_);
+            let score = -self.quiescence(game, -beta, -alpha, ply + 1, &mut child_pv);
+            game.pop_move();
+            if self.stopped {
+                return 0;
+            }
+            if score > alpha {
+                alpha = score;
+                pv.clear();
+                pv.push(move_);
+                pv.extend_from_slice(&child_pv);
+                if alpha >= beta {
+                    return beta;
+                }
+            }
+        }
+        if in_check && move_count == 0 {
+            return -MATE_SCORE + ply as i32;
+        }
+        alpha
+    }
+}
+
+fn figure_value(figure: Figure) -> i32 {
+    match figure {
+        Figure::Pawn => 100,
+        Figure::Knight => 320,
+        Figure::Bishop => 330,
+        Figure::Rook => 500,
+        Figure::Queen => 900,
+        Figure::King => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(fen: &str, limits: &SearchLimits) -> SearchResult {
+        let mut game = GameState::try_from_fen(fen).unwrap();
+        search(
+            &mut game,
+            &TranspositionTable::new(1),
+            limits,
+            &SearchOptions::default(),
+            &AtomicBool::new(false),
+            |_| (),
+        )
+    }
+
+    #[test]
+    fn test_finds_mate_in_one() {
+        let limits = SearchLimits {
+            depth: Some(2),
+            ..SearchLimits::default()
+        };
+        let result = run("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", &limits);
+        assert_eq!(result.best_move.unwrap().to_string(), "a1a8");
+        assert_eq!(result.score, MATE_SCORE - 1);
+    }
+
+    #[test]
+    fn test_wins_material() {
+        let limits = SearchLimits {
+            depth: Some(3),
+            ..SearchLimits::default()
+        };
+        let result = run("4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1", &limits);
+        assert_eq!(result.best_move.unwrap().to_string(), "d1d5");
+    }
+
+    #[test]
+    fn test_finds_mate_in_two() {
+        let fen = "k7/8/2K5/8/8/8/8/7R w - - 0 1";
+        let limits = SearchLimits {
+            depth: Some(5),
+            ..SearchLimits::default()
+        };
+        let result = run(fen, &limits);
+        assert_eq!(result.score, MATE_SCORE - 3);
+        assert_eq!(result.pv.len(), 3);
+        let mut game = GameState::try_from_fen(fen).unwrap();
+        for &move_ in &result.pv {
+            assert!(game.legal_moves().contains(&move_));
+            game.make_move(move_);
+        }
+        assert!(game.legal_moves().is_empty());
+        assert!(game.is_check());
+    }
+
+    #[test]
+    fn test_threads() {
+        let mut game = GameState::try_from_fen(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        )
+        .unwrap();
+        let fen = game.to_fen();
+        let limits = SearchLimits {
+            depth: Some(5),
+            ..SearchLimits::default()
+        };
+        let mut reported = Vec::new();
+        let result = search_threads(
+            &mut game,
+            &TranspositionTable::new(4),
+            &limits,
+            &SearchOptions::default(),
+            4,
+            &AtomicBool::new(false),
+            |info| reported.push(info.nodes),
+        );
+        assert_eq!(result.depth, 5);
+        assert!(game.legal_moves().contains(&result.best_move.unwrap()));
+        assert_eq!(game.to_fen(), fen);
+        // only this thread reports, but the node counts cover every thread
+        assert_eq!(reported.len(), 5);
+        assert!(reported.windows(2).all(|w| w[0] <= w[1]));
+        assert!(result.nodes >= *reported.last().unwrap());
+
+        let mut game = GameState::try_from_fen("k7/8/2K5/8/8/8/8/7R w - - 0 1").unwrap();
+        let result = search_threads(
+            &mut game,
+            &TranspositionTable::new(1),
+            &limits,
+            &SearchOptions::default(),
+            3,
+            &AtomicBool::new(false),
+            |_| (),
+        );
+        assert_eq!(result.score, MATE_SCORE - 3);
+    }
+
+    #[test]
+    fn test_multipv() {
+        let mut game = GameState::try_from_fen("4k3/8/8/3q4/8/8/1n6/3RK3 w - - 0 1").unwrap();
+        let limits = SearchLimits {
+            depth: Some(4),
+            multipv: 3,
+            ..SearchLimits::default()
+        };
+        let mut reported = Vec::new();
+        let result = search(
+            &mut game,
+            &TranspositionTable::new(1),
+            &limits,
+            &SearchOptions::default(),
+            &AtomicBool::new(false),
+            |info| reported.push((info.depth, info.multipv, info.score)),
+        );
+        assert_eq!(result.lines.len(), 3);
+        assert_eq!(result.lines[0].pv, result.pv);
+        assert_eq!(result.lines[0].score, result.score);
+        assert_eq!(result.best_move.unwrap().to_string(), "d1d5");
+        assert!(result.lines.windows(2).all(|w| w[0].score >= w[1].score));
+        let mut first_moves: Vec<Move> = result.lines.iter().map(|l| l.pv[0]).collect();
+        first_moves.dedup();
+        assert_eq!(first_moves.len(), 3);
+        assert!(first_moves.iter().all(|m| game.legal_moves().contains(m)));
+        assert_eq!(reported.len(), 12);
+        assert_eq!(
+            &reported[9..],
+            [
+                (4, 1, result.lines[0].score),
+                (4, 2, result.lines[1].score),
+                (4, 3, result.lines[2].score)
+            ]
+        );
+
+        // fewer legal moves than lines asked for
+        let limits = SearchLimits {
+            depth: Some(4),
+            multipv: 5,
+            ..SearchLimits::default()
+        };
+        let result = run("7k/8/8/8/8/8/P7/K7 w - - 0 1", &limits);
+        assert_eq!(result.lines.len(), 4);
+    }
+
+    #[test]
+    fn test_skill() {
+        let limits = SearchLimits {
+            skill: Some(Skill::new(2.0, 11)),
+            ..SearchLimits::default()
+        };
+        let result = run(crate::DEFAULT_FEN, &limits);
+        // no other limit is needed, the depth is capped by the level
+        assert_eq!(result.depth, 3);
+        assert_eq!(result.lines.len(), 4);
+        let line = result.lines.iter().find(|l| l.pv == result.pv).unwrap();
+        assert_eq!(result.best_move, line.pv.first().copied());
+        assert_eq!(result.score, line.score);
+    }
+
+    #[test]
+    fn test_reports_each_iteration() {
+        let mut game = GameState::default();
+        let limits = SearchLimits {
+            depth: Some(3),
+            ..SearchLimits::default()
+        };
+        let mut depths = Vec::new();
+        let result = search(
+            &mut game,
+            &TranspositionTable::new(1),
+            &limits,
+            &SearchOptions::default(),
+            &AtomicBool::new(false),
+            |info| {
+                depths.push(info.depth);
+                assert_eq!(info.pv.len() as u32, info.depth);
+            },
+        );
+        assert_eq!(depths, [1, 2, 3]);
+        assert_eq!(result.best_move, result.pv.first().copied());
+        assert_eq!(game.to_fen().as_ref(), crate::DEFAULT_FEN);
+    }
+
+    #[test]
+    fn test_draws_score_zero() {
+        let limits = SearchLimits {
+            depth: Some(3),
+            ..SearchLimits::default()
+        };
+        for fen in [
+            "4k3/8/8/8/8/8/8/4KN2 w - - 0 1",
+            "4k3/8/8/8/8/8/8/R3K3 w - - 99 80",
+            "7k/5Q2/6K1/8/8/8/8/8 b - - 0 1",
+        ] {
+            assert_eq!(run(fen, &limits).score, DRAW_SCORE, "{fen}");
+        }
+    }
+
+    #[test]
+    fn test_quiescence_sees_recapture() {
+        let limits = SearchLimits {
+            depth: Some(1),
+            ..SearchLimits::default()
+        };
+        let result = run("4k3/8/4p3/3p4/8/8/8/3QK3 w - - 0 1", &limits);
+        assert_ne!(result.best_move.unwrap().to_string(), "d1d5");
+        // a queen against two pawns, not a queen against one
+        assert!((600..900).contains(&result.score), "{}", result.score);
+    }
+
+    #[test]
+    fn test_quiescence_resolves_captures() {
+        // white wins the undefended knight but not the defended pawn
+        let mut game = GameState::try_from_fen("4k3/8/4p3/3p3n/8/8/8/3QK2R w - - 0 1").unwrap();
+        let (score, pv) = quiesce_pv(&mut game);
+        assert_eq!(pv, [game.parse_move("h1h5").unwrap()]);
+        game.make_move(pv[0]);
+        assert_eq!(score, -evaluate(&game));
+        // in check the stand pat is not available
+        let mut game = GameState::try_from_fen("4k3/8/8/8/8/8/3q4/4K3 w - - 0 1").unwrap();
+        let (score, pv) = quiesce_pv(&mut game);
+        assert_eq!(pv, [game.parse_move("e1d2").unwrap()]);
+        game.make_move(pv[0]);
+        assert_eq!(score, -evaluate(&game));
+        let mut game = GameState::try_from_fen("R5k1/5ppp/8/8/8/8/8/6K1 b - - 1 1").unwrap();
+        assert_eq!(quiesce_pv(&mut game), (-MATE_SCORE, Vec::new()));
+    }
+
+    #[test]
+    fn test_reuses_transposition_table() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let mut game = GameState::try_from_fen(fen).unwrap();
+        let limits = SearchLimits {
+            depth: Some(4),
+            ..SearchLimits::default()
+        };
+        let tt = TranspositionTable::new(1);
+        let first = search(
+            &mut game,
+            &tt,
+            &limits,
+            &SearchOptions::default(),
+            &AtomicBool::new(false),
+            |_| (),
+        );
+        let second = search(
+            &mut game,
+            &tt,
+            &limits,
+            &SearchOptions::default(),
+            &AtomicBool::new(false),
+            |_| (),
+        );
+        assert_eq!(first.score, second.score);
+        assert!(second.nodes < first.nodes);
+    }
+
+    #[test]
+    fn test_selectivity_saves_nodes() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let limits = SearchLimits {
+            depth: Some(5),
+            ..SearchLimits::default()
+        };
+        let mut nodes = Vec::new();
+        for options in [SearchOptions::NONE, SearchOptions::default()] {
+            let mut game = GameState::try_from_fen(fen).unwrap();
+            let tt = TranspositionTable::new(1);
+            let result = search(
+                &mut game,
+                &tt,
+                &limits,
+                &options,
+                &AtomicBool::new(false),
+                |_| (),
+            );
+            assert_eq!(game.to_fen().as_ref(), fen);
+            nodes.push(result.nodes);
+        }
+        assert!(nodes[1] < nodes[0], "{nodes:?}");
+    }
+
+    #[test]
+    fn test_each_option_finds_mate() {
+        let limits = SearchLimits {
+            depth: Some(5),
+            ..SearchLimits::default()
+        };
+        let toggles: [fn(&mut SearchOptions) -> &mut bool; 8] = [
+            |o| &mut o.null_move,
+            |o| &mut o.late_move_reductions,
+            |o| &mut o.reverse_futility,
+            |o| &mut o.futility,
+            |o| &mut o.razoring,
+            |o| &mut o.check_extensions,
+            |o| &mut o.singular_extensions,
+            |o| &mut o.aspiration_windows,
+        ];
+        for toggle in toggles {
+            let mut options = SearchOptions::NONE;
+            *toggle(&mut options) = true;
+            let mut game = GameState::try_from_fen("k7/8/2K5/8/8/8/8/7R w - - 0 1").unwrap();
+            let tt = TranspositionTable::new(1);
+            let result = search(
+                &mut game,
+                &tt,
+                &limits,
+                &options,
+                &AtomicBool::new(false),
+                |_| (),
+            );
+            assert_eq!(result.score, MATE_SCORE - 3, "{options:?}");
+        }
+    }
+
+    #[test]
+    fn test_null_move_guard() {
+        let game = GameState::try_from_fen("4k3/4p3/8/8/8/8/4P3/4K3 w - - 0 1").unwrap();
+        assert!(!has_non_pawn_material(&game));
+        let game = GameState::try_from_fen("4k3/4p3/8/8/8/8/4P3/4KN2 b - - 0 1").unwrap();
+        assert!(!has_non_pawn_material(&game));
+        let game = GameState::try_from_fen("4k3/4p3/8/8/8/8/4P3/4KN2 w - - 0 1").unwrap();
+        assert!(has_non_pawn_material(&game));
+    }
+
+    #[test]
+    fn test_lmr_reduction() {
+        assert_eq!(lmr_reduction(2, 2, false), 0);
+        assert!(lmr_reduction(10, 20, false) >= 3);
+        assert_eq!(
+            lmr_reduction(10, 20, true) + 1,
+            lmr_reduction(10, 20, false)
+        );
+    }
+
+    #[test]
+    fn test_stops_on_flag() {
+        let mut game = GameState::default();
+        let result = search(
+            &mut game,
+            &TranspositionTable::new(1),
+            &SearchLimits::default(),
+            &SearchOptions::default(),
+            &AtomicBool::new(true),
+            |_| (),
+        );
+        assert!(result.best_move.is_some());
+        assert_eq!(result.depth, 0);
+    }
+
+    #[test]
+    fn test_respects_clock() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let limits = SearchLimits {
+            clock: Some(Clock {
+                time: Duration::from_millis(210),
+                increment: Duration::ZERO,
+                moves_to_go: Some(1),
+            }),
+            move_overhead: Duration::from_millis(10),
+            ..SearchLimits::default()
+        };
+        let start = Instant::now();
+        let result = run(fen, &limits);
+        // the hard limit is 150ms, leave room for a slow machine
+        assert!(start.elapsed() < Duration::from_millis(400));
+        assert!(result.best_move.is_some());
+    }
+}
@@ -0,0 +1,345 @@
+use super::{figure_value, MAX_PLY};
+use crate::{
+    gamestate::{GameState, GenMode, Move},
+    pieces::{Color, Figure},
+};
+use alloc::{boxed::Box, vec::Vec};
+
+const MAX_HISTORY: i32 = 16_384;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Stage {
+    TTMove,
+    GenerateCaptures,
+    GoodCaptures,
+    Killers,
+    CounterMove,
+    GenerateQuiets,
+    Quiets,
+    BadCaptures,
+    Evasions,
+    Done,
+}
+
+// move ordering statistics gathered during a search
+pub struct Heuristics {
+    killers: Box<[[Option<Move>; 2]]>,
+    history: Box<[[[i32; 64]; 64]; 2]>,
+    counter_moves: Box<[[Option<Move>; 64]; 12]>,
+}
+
+impl Heuristics {
+    pub fn new() -> Self {
+        Self {
+            killers: vec![[None; 2]; MAX_PLY as usize + 1].into_boxed_slice(),
+            history: Box::new([[[0; 64]; 64]; 2]),
+            counter_moves: Box::new([[None; 64]; 12]),
+        }
+    }
+
+    pub fn clear_killers(&mut self, ply: u32) {
+        if let Some(killers) = self.killers.get_mut(ply as usize) {
+            *killers = [None; 2];
+        }
+    }
+
+    pub fn get_history(&self, color: Color, move_: Move) -> i32 {
+        self.history[color as usize][move_.get_from().as_usize()][move_.get_to().as_usize()]
+    }
+
+    pub fn get_counter_move(&self, game: &GameState) -> Option<Move> {
+        let (piece, to) = last_move_key(game)?;
+        self.counter_moves[piece][to]
+    }
+
+    // called when a quiet move caused a beta cutoff, with the quiets that
+    // were searched before it and failed
+    pub fn update_quiet(
+        &mut self,
+        game: &GameState,
+        ply: u32,
+        depth: u32,
+        best: Move,
+        failed: &[Move],
+    ) {
+        let killers = &mut self.killers[(ply as usize).min(MAX_PLY as usize)];
+        if killers[0] != Some(best) {
+            killers[1] = killers[0];
+            killers[0] = Some(best);
+        }
+        if let Some((piece, to)) = last_move_key(game) {
+            self.counter_moves[piece][to] = Some(best);
+        }
+        let bonus = (depth * depth).min(MAX_HISTORY as u32) as i32;
+        let color = game.turn();
+        self.add_history(color, best, bonus);
+        for &move_ in failed {
+            self.add_history(color, move_, -bonus);
+        }
+    }
+
+    // history gravity keeps scores within +-MAX_HISTORY
+    fn add_history(&mut self, color: Color, move_: Move, bonus: i32) {
+        let entry = &mut self.history[color as usize][move_.get_from().as_usize()]
+            [move_.get_to().as_usize()];
+        *entry += bonus - *entry * bonus.abs() / MAX_HISTORY;
+    }
+}
+
+impl Default for Heuristics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn last_move_key(game: &GameState) -> Option<(usize, usize)> {
+    let to = game.history().last()?.move_.get_to();
+    let piece = game.board().get_square(to)?;
+    Some((u8::from(piece) as usize, to.as_usize()))
+}
+
+pub fn is_tactical(game: &GameState, move_: Move) -> bool {
+    matches!(move_, Move::EnPassant { .. } | Move::PromotePawn { .. })
+        || game.board().get_square(move_.get_to()).is_some()
+}
+
+// yields moves lazily in stages, so the expensive later stages are never
+// generated when an early move causes a cutoff
+pub struct MovePicker {
+    stage: Stage,
+    tt_move: Option<Move>,
+    killers: [Option<Move>; 2],
+    killer_idx: usize,
+    counter_move: Option<Move>,
+    moves: Vec<(Move, i32)>,
+    bad_captures: Vec<(Move, i32)>,
+    skip_quiets: bool,
+}
+
+impl MovePicker {
+    pub fn new(game: &GameState, heuristics: &Heuristics, tt_move: Option<Move>, ply: u32) -> Self {
+        let in_check = game.is_check();
+        let killers = heuristics
+            .killers
+            .get(ply as usize)
+            .copied()
+            .unwrap_or_default();
+        Self {
+            stage: match in_check {
+                true => Stage::Evasions,
+                false => Stage::TTMove,
+            },
+            tt_move,
+            killers,
+            killer_idx: 0,
+            counter_move: heuristics.get_counter_move(game),
+            moves: Vec::new(),
+            bad_captures: Vec::new(),
+            skip_quiets: false,
+        }
+    }
+
+    // captures and promotions only, for the quiescence search
+    pub fn new_tactical(game: &GameState, heuristics: &Heuristics) -> Self {
+        let mut picker = Self::new(game, heuristics, None, 0);
+        picker.skip_quiets = true;
+        picker
+    }
+
+    pub fn next(&mut self, game: &GameState, heuristics: &Heuristics) -> Option<Move> {
+        loop {
+            match self.stage {
+                Stage::TTMove => {
+                    self.stage = Stage::GenerateCaptures;
+                    if let Some(move_) = self.tt_move {
+                        return Some(move_);
+                    }
+                }
+                Stage::GenerateCaptures => {
+                    self.moves = game
+                        .generate_moves(GenMode::Tactical)
+                        .into_iter()
+                        .filter(|&m| Some(m) != self.tt_move)
+                        .map(|m| (m, mvv_lva(game, m)))
+                        .collect();
+                    self.stage = Stage::GoodCaptures;
+                }
+                Stage::GoodCaptures => match pick_best(&mut self.moves) {
+                    Some((move_, _)) if game.see_ge(move_, 0) => return Some(move_),
+                    Some(bad) => self.bad_captures.push(bad),
+                    None => {
+                        self.stage = match self.skip_quiets {
+                            true => Stage::BadCaptures,
+                            false => Stage::Killers,
+                        }
+                    }
+                },
+                Stage::Killers => match self.killers.get(self.killer_idx) {
+                    Some(&killer) => {
+                        self.killer_idx += 1;
+                        if let Some(move_) = killer.filter(|&m| self.is_new_quiet(game, m)) {
+                            return Some(move_);
+                        }
+                    }
+                    None => self.stage = Stage::CounterMove,
+                },
+                Stage::CounterMove => {
+                    self.stage = Stage::GenerateQuiets;
+                    if let Some(move_) = self
+                        .counter_move
+                        .filter(|&m| !self.killers_contain(m) && self.is_new_quiet(game, m))
+                    {
+                        return Some(move_);
+                    }
+                }
+                Stage::GenerateQuiets => {
+                    let color = game.turn();
+                    self.moves = game
+                        .generate_moves(GenMode::Quiet)
+                        .into_iter()
+                        .filter(|&m| {
+                            Some(m) != self.tt_move
+                                && Some(m) != self.counter_move
+                                && !self.killers_contain(m)
+                        })
+                        .map(|m| (m, heuristics.get_history(color, m)))
+                        .collect();
+                    self.stage = Stage::Quiets;
+                }
+                Stage::Quiets => match pick_best(&mut self.moves) {
+                    Some((move_, _)) => return Some(move_),
+                    None => self.stage = Stage::BadCaptures,
+                },
+                Stage::BadCaptures => match pick_best(&mut self.bad_captures) {
+                    Some((move_, _)) => return Some(move_),
+                    None => self.stage = Stage::Done,
+                },
+                Stage::Evasions => {
+                    let color = game.turn();
+                    self.moves = game
+                        .generate_moves(GenMode::Evasions)
+                        .into_iter()
+                        .map(|m| {
+                            let score = match m {
+                                _ if Some(m) == self.tt_move => i32::MAX,
+                                _ if is_tactical(game, m) => MAX_HISTORY + mvv_lva(game, m),
+                                _ => heuristics.get_history(color, m),
+                            };
+                            (m, score)
+                        })
+                        .collect();
+                    self.stage = Stage::Done;
+                }
+                Stage::Done => return pick_best(&mut self.moves).map(|(move_, _)| move_),
+            }
+        }
+    }
+
+    // killers and counter-moves come from other positions, so check them
+    // against this one before trying them
+    fn is_new_quiet(&self, game: &GameState, move_: Move) -> bool {
+        Some(move_) != self.tt_move
+            && !is_tactical(game, move_)
+            && game.move_from_u16(move_.to_u16()) == Some(move_)
+    }
+
+    fn killers_contain(&self, move_: Move) -> bool {
+        self.killers.contains(&Some(move_))
+    }
+}
+
+fn mvv_lva(game: &GameState, move_: Move) -> i32 {
+    let victim = match move_ {
+        Move::EnPassant { .. } => figure_value(Figure::Pawn),
+        _ => game
+            .board()
+            .get_square(move_.get_to())
+            .map_or(0, |p| figure_value(p.figure)),
+    };
+    let promotion = move_.get_promotion().map_or(0, |p| figure_value(p.figure));
+    let attacker = game
+        .board()
+        .get_square(move_.get_from())
+        .map_or(0, |p| figure_value(p.figure));
+    10 * (victim + promotion) - attacker / 10
+}
+
+// selection sort step, cheaper than sorting when a cutoff comes early
+fn pick_best(moves: &mut Vec<(Move, i32)>) -> Option<(Move, i32)> {
+    let best = moves
+        .iter()
+        .enumerate()
+        .max_by_key(|(_, (_, score))| *score)
+        .map(|(idx, _)| idx)?;
+    Some(moves.swap_remove(best))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collect(game: &GameState, heuristics: &Heuristics, tt_move: Option<Move>) -> Vec<Move> {
+        let mut picker = MovePicker::new(game, heuristics, tt_move, 1);
+        let mut moves = Vec::new();
+        while let Some(move_) = picker.next(game, heuristics) {
+            moves.push(move_);
+        }
+        moves
+    }
+
+    #[test]
+    fn test_picker_yields_each_legal_move_once() {
+        let mut heuristics = Heuristics::new();
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        ] {
+            let mut game = GameState::try_from_fen(fen).unwrap();
+            for first in game.legal_moves() {
+                game.make_move(first);
+                let mut legal = game.legal_moves();
+                // stale killers and counter-moves from a sibling position
+                if let Some(&quiet) = legal.iter().find(|&&m| !is_tactical(&game, m)) {
+                    heuristics.update_quiet(&game, 1, 3, quiet, &[]);
+                }
+                let tt_move = legal.last().copied();
+                let mut picked = collect(&game, &heuristics, tt_move);
+                assert_eq!(picked.first().copied(), tt_move);
+                legal.sort_by_key(|m| m.to_u16());
+                picked.sort_by_key(|m| m.to_u16());
+                assert_eq!(picked, legal, "{}", game.to_fen());
+                game.pop_move();
+            }
+        }
+    }
+
+    #[test]
+    fn test_picker_stage_order() {
+        // Qxd5 wins a pawn, Rxa6 loses the rook for a knight
+        let game = GameState::try_from_fen("4k3/1p6/n7/3p4/8/8/8/R2QK3 w - - 0 1").unwrap();
+        let mut heuristics = Heuristics::new();
+        let killer = game.parse_move("e1f2").unwrap();
+        heuristics.update_quiet(&game, 1, 1, killer, &[]);
+        let moves = collect(&game, &heuristics, None);
+        assert_eq!(moves.first().unwrap().to_string(), "d1d5");
+        assert_eq!(moves[1], killer);
+        assert_eq!(moves.last().unwrap().to_string(), "a1a6");
+    }
+
+    #[test]
+    fn test_history_ordering() {
+        let game = GameState::default();
+        let mut heuristics = Heuristics::new();
+        let good = game.parse_move("g1f3").unwrap();
+        let bad = game.parse_move("a2a3").unwrap();
+        heuristics.update_quiet(&game, 5, 4, good, &[bad]);
+        heuristics.clear_killers(5);
+        assert!(heuristics.get_history(Color::White, good) > 0);
+        assert!(heuristics.get_history(Color::White, bad) < 0);
+        let moves = collect(&game, &heuristics, None);
+        assert_eq!(moves.first(), Some(&good));
+        assert_eq!(moves.last(), Some(&bad));
+    }
+}
@@ -0,0 +1,245 @@
+use super::{
+    search_threads, SearchInfo, SearchLimits, SearchOptions, SearchResult, TranspositionTable,
+    DEFAULT_HASH_MB,
+};
+use crate::gamestate::GameState;
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+};
+
+// runs searches in the background for callers that don't speak uci. The
+// transposition table is kept between searches.
+pub struct Searcher {
+    tt: Arc<TranspositionTable>,
+    options: SearchOptions,
+    threads: usize,
+}
+
+impl Default for Searcher {
+    fn default() -> Self {
+        Self::new(DEFAULT_HASH_MB)
+    }
+}
+
+impl Searcher {
+    pub fn new(hash_mb: usize) -> Self {
+        Self {
+            tt: Arc::new(TranspositionTable::new(hash_mb)),
+            options: SearchOptions::default(),
+            threads: 1,
+        }
+    }
+
+    pub fn set_options(&mut self, options: SearchOptions) {
+        self.options = options;
+    }
+
+    pub fn set_threads(&mut self, threads: usize) {
+        self.threads = threads.max(1);
+    }
+
+    pub fn get_tt(&self) -> &TranspositionTable {
+        &self.tt
+    }
+
+    // every completed iteration is sent to the handle's info channel
+    pub fn start(&self, position: &GameState, limits: SearchLimits) -> SearchHandle {
+        let (sender, receiver) = mpsc::channel();
+        let mut handle = self.start_with(position, limits, move |info| {
+            // nobody listening is fine
+            let _ = sender.send(info.clone());
+        });
+        handle.info = Some(receiver);
+        handle
+    }
+
+    // calls `on_info` from the search thread after every completed iteration
+    pub fn start_with(
+        &self,
+        position: &GameState,
+        limits: SearchLimits,
+        on_info: impl FnMut(&SearchInfo) + Send + 'static,
+    ) -> SearchHandle {
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = Arc::clone(&stop);
+        let ponder = limits.ponder.clone();
+        let tt = Arc::clone(&self.tt);
+        let (options, threads) = (self.options, self.threads);
+        let mut game = position.clone();
+        let thread = thread::spawn(move || {
+            search_threads(
+                &mut game,
+                &tt,
+                &limits,
+                &options,
+                threads,
+                &thread_stop,
+                on_info,
+            )
+        });
+        SearchHandle {
+            stop,
+            ponder,
+            thread,
+            info: None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct SearchHandle {
+    stop: Arc<AtomicBool>,
+    ponder: Option<Arc<AtomicBool>>,
+    thread: JoinHandle<SearchResult>,
+    info: Option<Receiver<SearchInfo>>,
+}
+
+impl SearchHandle {
+    // the search still returns the best move of its last completed iteration
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+
+    // starts the clock of a search started with SearchLimits::ponder
+    pub fn ponderhit(&self) {
+        if let Some(ponder) = &self.ponder {
+            ponder.store(false, Ordering::Relaxed);
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.thread.is_finished()
+    }
+
+    // None when the search was started with a callback
+    pub fn info(&self) -> Option<&Receiver<SearchInfo>> {
+        self.info.as_ref()
+    }
+
+    pub fn wait(self) -> SearchResult {
+        self.thread.join().expect("search thread panicked")
+    }
+
+    pub fn stop_and_wait(self) -> SearchResult {
+        self.stop();
+        self.wait()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gamestate::Move;
+    use crate::search::MATE_SCORE;
+    use alloc::vec::Vec;
+    use std::{
+        sync::Mutex,
+        time::{Duration, Instant},
+    };
+
+    #[test]
+    fn test_start_and_wait() {
+        let searcher = Searcher::new(1);
+        let game = GameState::default();
+        let limits = SearchLimits {
+            depth: Some(4),
+            ..SearchLimits::default()
+        };
+        let handle = searcher.start(&game, limits);
+        let result = handle.wait();
+        assert_eq!(result.depth, 4);
+        assert!(game.legal_moves().contains(&result.best_move.unwrap()));
+
+        let handle = searcher.start(
+            &game,
+            SearchLimits {
+                depth: Some(4),
+                ..SearchLimits::default()
+            },
+        );
+        let infos: Vec<SearchInfo> = handle.info().unwrap().iter().collect();
+        assert_eq!(
+            infos.iter().map(|i| i.depth).collect::<Vec<_>>(),
+            [1, 2, 3, 4]
+        );
+        assert!(infos
+            .iter()
+            .all(|i| i.seldepth >= i.depth && !i.pv.is_empty()));
+        assert_eq!(handle.wait().pv, infos[3].pv);
+    }
+
+    #[test]
+    fn test_stop() {
+        let searcher = Searcher::default();
+        let game = GameState::default();
+        let limits = SearchLimits {
+            infinite: true,
+            ..SearchLimits::default()
+        };
+        let depths = Arc::new(Mutex::new(Vec::new()));
+        let reported = Arc::clone(&depths);
+        let handle = searcher.start_with(&game, limits, move |info| {
+            reported.lock().unwrap().push(info.depth)
+        });
+        assert!(handle.info().is_none());
+        while depths.lock().unwrap().is_empty() {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert!(!handle.is_finished());
+        let start = Instant::now();
+        let result = handle.stop_and_wait();
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert!(result.best_move.is_some());
+    }
+
+    #[test]
+    fn test_ponder() {
+        let searcher = Searcher::new(1);
+        let game = GameState::default();
+        let limits = SearchLimits {
+            movetime: Some(Duration::from_millis(50)),
+            ponder: Some(Arc::new(AtomicBool::new(true))),
+            ..SearchLimits::default()
+        };
+        let handle = searcher.start(&game, limits);
+        // no time limit applies while pondering
+        std::thread::sleep(Duration::from_millis(200));
+        assert!(!handle.is_finished());
+        let start = Instant::now();
+        handle.ponderhit();
+        let result = handle.wait();
+        // the movetime counts from the ponderhit, not from the start
+        assert!(start.elapsed() >= Duration::from_millis(40));
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert!(result.depth > 1);
+    }
+
+    #[test]
+    fn test_limits() {
+        let searcher = Searcher::new(1);
+        let game = GameState::try_from_fen("k7/8/2K5/8/8/8/8/7R w - - 0 1").unwrap();
+        // mate in two is found without a depth limit
+        let limits = SearchLimits {
+            mate: Some(2),
+            ..SearchLimits::default()
+        };
+        let result = searcher.start(&game, limits).wait();
+        assert_eq!(result.score, MATE_SCORE - 3);
+
+        // whatever the best move is, only these three may be played
+        let searchmoves: Vec<Move> = game.legal_moves().into_iter().rev().take(3).collect();
+        let limits = SearchLimits {
+            depth: Some(3),
+            searchmoves: searchmoves.clone(),
+            ..SearchLimits::default()
+        };
+        let result = searcher.start(&game, limits).wait();
+        assert!(searchmoves.contains(&result.best_move.unwrap()));
+        assert!(result.pv.first().is_some_and(|m| searchmoves.contains(m)));
+    }
+}
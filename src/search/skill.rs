@@ -0,0 +1,146 @@
+use super::PvLine;
+
+pub const MAX_SKILL_LEVEL: u32 = 20;
+// the ratings UCI_Elo accepts, spread linearly over the levels. These are
+// placeholders until the calibrate bin has been run against rated opponents.
+pub const MIN_ELO: u32 = 1000;
+pub const MAX_ELO: u32 = 2400;
+// enough candidates to sometimes play a second or third choice
+const SKILL_MULTIPV: usize = 4;
+const PAWN_SCORE: i32 = 100;
+
+// weakens the search by capping its depth and then picking among the best
+// few root moves at random, favouring the better ones more the higher the
+// level. Level MAX_SKILL_LEVEL is full strength.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Skill {
+    level: f64,
+    seed: u64,
+}
+
+impl Skill {
+    pub fn new(level: f64, seed: u64) -> Self {
+        Self {
+            level: level.clamp(0.0, MAX_SKILL_LEVEL as f64),
+            seed,
+        }
+    }
+
+    pub fn from_elo(elo: u32, seed: u64) -> Self {
+        let elo = elo.clamp(MIN_ELO, MAX_ELO);
+        let level = (elo - MIN_ELO) as f64 / (MAX_ELO - MIN_ELO) as f64;
+        Self::new(level * MAX_SKILL_LEVEL as f64, seed)
+    }
+
+    pub fn get_level(&self) -> f64 {
+        self.level
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.level < MAX_SKILL_LEVEL as f64
+    }
+
+    pub fn get_depth(&self) -> u32 {
+        1 + self.level as u32
+    }
+
+    pub fn get_multipv(&self) -> usize {
+        SKILL_MULTIPV
+    }
+
+    // lines must be sorted best first. Every line gets a push towards the
+    // best score plus a random bonus, both bigger the weaker the level.
+    pub fn pick_line<'a>(&self, lines: &'a [PvLine]) -> Option<&'a PvLine> {
+        let (first, last) = (lines.first()?, lines.last()?);
+        let weakness = 120.0 - 2.0 * self.level;
+        let delta = (first.score - last.score).min(PAWN_SCORE) as f64;
+        let mut state = self.seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1;
+        let mut best = (f64::NEG_INFINITY, first);
+        for line in lines {
+            // xorshift64*
+            state ^= state >> 12;
+            state ^= state << 25;
+            state ^= state >> 27;
+            let random = state.wrapping_mul(0x2545_f491_4f6c_dd1d) % weakness as u64;
+            let push =
+                (weakness * (first.score - line.score) as f64 + delta * random as f64) / 128.0;
+            let score = line.score as f64 + push;
+            if score >= best.0 {
+                best = (score, line);
+            }
+        }
+        Some(best.1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gamestate::GameState;
+    use alloc::vec::Vec;
+
+    fn lines(scores: &[i32]) -> Vec<PvLine> {
+        let game = GameState::default();
+        game.legal_moves()
+            .into_iter()
+            .zip(scores)
+            .map(|(move_, &score)| PvLine {
+                score,
+                pv: vec![move_],
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_from_elo() {
+        assert_eq!(Skill::from_elo(0, 0).get_level(), 0.0);
+        assert_eq!(Skill::from_elo(MAX_ELO, 0).get_level(), 20.0);
+        assert_eq!(
+            Skill::from_elo((MIN_ELO + MAX_ELO) / 2, 0).get_level(),
+            10.0
+        );
+        assert!(!Skill::from_elo(3000, 0).is_enabled());
+        assert_eq!(Skill::new(7.5, 0).get_depth(), 8);
+    }
+
+    #[test]
+    fn test_pick_line() {
+        let candidates = lines(&[30, 25, 20, 15]);
+        assert_eq!(Skill::new(5.0, 1).pick_line(&[]), None);
+        assert_eq!(
+            Skill::new(0.0, 1).pick_line(&candidates[..1]),
+            Some(&candidates[0])
+        );
+        // close alternatives all get played at the lowest level
+        let picked = |level| {
+            (0..200)
+                .map(|seed| {
+                    Skill::new(level, seed)
+                        .pick_line(&candidates)
+                        .unwrap()
+                        .score
+                })
+                .collect::<Vec<_>>()
+        };
+        let weak = picked(0.0);
+        assert!([30, 25, 20, 15].iter().all(|score| weak.contains(score)));
+        // from the middle levels up a blunder is never chosen
+        let candidates = lines(&[200, -500]);
+        let blunders = |level| {
+            (0..200)
+                .filter(|&seed| {
+                    Skill::new(level, seed)
+                        .pick_line(&candidates)
+                        .unwrap()
+                        .score
+                        < 0
+                })
+                .count()
+        };
+        assert!(blunders(0.0) > 0);
+        assert_eq!(blunders(10.0), 0);
+        // the best move is chosen more often the higher the level
+        let best = |level| picked(level).iter().filter(|&&s| s == 30).count();
+        assert!(best(18.0) > best(0.0));
+    }
+}
@@ -0,0 +1,184 @@
+use super::SearchLimits;
+use crate::gamestate::Move;
+use std::time::Duration;
+
+pub const DEFAULT_MOVE_OVERHEAD: Duration = Duration::from_millis(10);
+const DEFAULT_MOVES_TO_GO: u32 = 30;
+const MAX_MOVES_TO_GO: u32 = 50;
+const MIN_MOVE_TIME: Duration = Duration::from_millis(1);
+// share of the remaining time a single move may ever take
+const MAX_TIME_FRACTION: f64 = 0.75;
+// how far past the planned time a move may run before it is cut off
+const HARD_LIMIT_FACTOR: u32 = 4;
+// indexed by how many iterations in a row returned the same best move
+const STABILITY_SCALES: [f64; 6] = [1.6, 1.3, 1.1, 1.0, 0.9, 0.8];
+const SCORE_DROP_WINDOW: i32 = 100;
+
+// the clock of the side to move
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Clock {
+    pub time: Duration,
+    pub increment: Duration,
+    pub moves_to_go: Option<u32>,
+}
+
+// plans how long a move may take. The search stops at the hard limit, and
+// between iterations once the soft limit scaled by how settled the search
+// looks has passed.
+#[derive(Debug, Clone)]
+pub struct TimeManager {
+    soft: Option<Duration>,
+    hard: Option<Duration>,
+    scale: f64,
+    best_move: Option<Move>,
+    stability: usize,
+    prev_score: Option<i32>,
+}
+
+impl TimeManager {
+    pub fn new(limits: &SearchLimits) -> Self {
+        let (soft, hard) = match (limits.infinite, limits.movetime, limits.clock) {
+            (true, _, _) => (None, None),
+            (false, Some(movetime), _) => {
+                let movetime = movetime.saturating_sub(limits.move_overhead);
+                (None, Some(movetime.max(MIN_MOVE_TIME)))
+            }
+            (false, None, Some(clock)) => {
+                let (soft, hard) = plan_move(&clock, limits.move_overhead);
+                (Some(soft), Some(hard))
+            }
+            (false, None, None) => (None, None),
+        };
+        Self {
+            soft,
+            hard,
+            scale: 1.0,
+            best_move: None,
+            stability: 0,
+            prev_score: None,
+        }
+    }
+
+    pub fn get_soft_limit(&self) -> Option<Duration> {
+        self.soft.map(|soft| soft.mul_f64(self.scale))
+    }
+
+    pub fn get_hard_limit(&self) -> Option<Duration> {
+        self.hard
+    }
+
+    pub fn is_hard_limit_reached(&self, elapsed: Duration) -> bool {
+        self.hard.is_some_and(|hard| elapsed >= hard)
+    }
+
+    // checked between iterations, there is no point starting one that can't
+    // finish
+    pub fn is_soft_limit_reached(&self, elapsed: Duration) -> bool {
+        self.get_soft_limit().is_some_and(|soft| elapsed >= soft)
+            || self.is_hard_limit_reached(elapsed)
+    }
+
+    // called after every completed iteration with the share of the root
+    // nodes that went to the best move
+    pub fn update(&mut self, best_move: Move, score: i32, best_move_nodes: u64, nodes: u64) {
+        self.stability = match self.best_move == Some(best_move) {
+            true => (self.stability + 1).min(STABILITY_SCALES.len() - 1),
+            false => 0,
+        };
+        self.best_move = Some(best_move);
+        let stability_scale = STABILITY_SCALES[self.stability];
+        // spend more while the score is falling
+        let drop = self.prev_score.map_or(0, |prev| prev - score);
+        let drop_scale =
+            1.0 + drop.clamp(0, SCORE_DROP_WINDOW) as f64 / SCORE_DROP_WINDOW as f64 / 2.0;
+        self.prev_score = Some(score);
+        // a best move that took most of the effort is unlikely to change
+        let fraction = best_move_nodes as f64 / nodes.max(1) as f64;
+        let nodes_scale = 1.5 - fraction.clamp(0.0, 1.0);
+        self.scale = stability_scale * drop_scale * nodes_scale;
+    }
+}
+
+fn plan_move(clock: &Clock, move_overhead: Duration) -> (Duration, Duration) {
+    let available = clock.time.saturating_sub(move_overhead).max(MIN_MOVE_TIME);
+    let moves_to_go = clock
+        .moves_to_go
+        .unwrap_or(DEFAULT_MOVES_TO_GO)
+        .clamp(1, MAX_MOVES_TO_GO);
+    let max_time = available.mul_f64(MAX_TIME_FRACTION).max(MIN_MOVE_TIME);
+    let planned = available / moves_to_go + clock.increment * 3 / 4;
+    let soft = planned.min(max_time).max(MIN_MOVE_TIME);
+    let hard = (soft * HARD_LIMIT_FACTOR).min(max_time);
+    (soft, hard)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gamestate::GameState;
+
+    fn clock_limits(time: u64, increment: u64, moves_to_go: Option<u32>) -> SearchLimits {
+        SearchLimits {
+            clock: Some(Clock {
+                time: Duration::from_millis(time),
+                increment: Duration::from_millis(increment),
+                moves_to_go,
+            }),
+            move_overhead: Duration::from_millis(10),
+            ..SearchLimits::default()
+        }
+    }
+
+    #[test]
+    fn test_limits() {
+        let time = TimeManager::new(&clock_limits(60_010, 1000, Some(20)));
+        assert_eq!(time.get_soft_limit(), Some(Duration::from_millis(3750)));
+        assert_eq!(time.get_hard_limit(), Some(Duration::from_millis(15000)));
+        // the last move before the time control can't use everything
+        let time = TimeManager::new(&clock_limits(1010, 0, Some(1)));
+        assert_eq!(time.get_soft_limit(), Some(Duration::from_millis(750)));
+        assert_eq!(time.get_hard_limit(), Some(Duration::from_millis(750)));
+        // nothing left but the overhead
+        let time = TimeManager::new(&clock_limits(5, 0, None));
+        assert_eq!(time.get_hard_limit(), Some(MIN_MOVE_TIME));
+
+        let limits = SearchLimits {
+            movetime: Some(Duration::from_millis(500)),
+            move_overhead: Duration::from_millis(10),
+            ..SearchLimits::default()
+        };
+        let time = TimeManager::new(&limits);
+        assert_eq!(time.get_soft_limit(), None);
+        assert_eq!(time.get_hard_limit(), Some(Duration::from_millis(490)));
+        assert!(!time.is_soft_limit_reached(Duration::from_millis(489)));
+        assert!(time.is_soft_limit_reached(Duration::from_millis(490)));
+
+        let limits = SearchLimits {
+            infinite: true,
+            ..clock_limits(1000, 0, None)
+        };
+        let time = TimeManager::new(&limits);
+        assert!(!time.is_soft_limit_reached(Duration::from_secs(3600)));
+    }
+
+    #[test]
+    fn test_adjustments() {
+        let game = GameState::default();
+        let moves = game.legal_moves();
+        let mut time = TimeManager::new(&clock_limits(60_010, 0, Some(20)));
+        let base = time.get_soft_limit().unwrap();
+        // a new best move that only got half the nodes
+        time.update(moves[0], 20, 500, 1000);
+        let unstable = time.get_soft_limit().unwrap();
+        assert!(unstable > base);
+        for _ in 0..10 {
+            time.update(moves[0], 20, 900, 1000);
+        }
+        let stable = time.get_soft_limit().unwrap();
+        assert!(stable < base);
+        time.update(moves[0], -80, 900, 1000);
+        assert!(time.get_soft_limit().unwrap() > stable);
+        time.update(moves[1], -80, 900, 1000);
+        assert!(time.get_soft_limit().unwrap() > stable);
+    }
+}
@@ -0,0 +1,395 @@
+use super::{is_mate_score, MATE_SCORE};
+use crate::gamestate::GameState;
+use alloc::vec::Vec;
+use core::{
+    mem::size_of,
+    sync::atomic::{AtomicU64, AtomicU8, Ordering},
+};
+
+pub const DEFAULT_HASH_MB: usize = 16;
+const BUCKET_SIZE: usize = 4;
+const GENERATION_MASK: u8 = 0b0011_1111;
+// perft counts share the slots with search entries. Their keys are mixed with
+// the depth and their data leaves the bound bits clear, so a search never
+// mistakes one for its own entry and replaces it like an empty slot.
+const PERFT_KEY: u64 = 0x6c62_272e_07bb_0142;
+const MAX_PERFT_NODES: u64 = (1 << 62) - 1;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Bound {
+    Exact = 1,
+    Lower,
+    Upper,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TTEntry {
+    pub move16: u16,
+    pub score: i32,
+    pub eval: i32,
+    pub depth: u8,
+    pub bound: Bound,
+    pub generation: u8,
+}
+
+impl TTEntry {
+    // layout: move | score | eval | depth | bound << 6 | generation
+    fn pack(self) -> u64 {
+        self.move16 as u64
+            | (self.score as i16 as u16 as u64) << 16
+            | (self.eval as i16 as u16 as u64) << 32
+            | (self.depth as u64) << 48
+            | ((self.bound as u64) << 6 | self.generation as u64) << 56
+    }
+
+    fn unpack(data: u64) -> Option<Self> {
+        let bound = match (data >> 62) & 0b11 {
+            1 => Bound::Exact,
+            2 => Bound::Lower,
+            3 => Bound::Upper,
+            _ => return None,
+        };
+        Some(Self {
+            move16: data as u16,
+            score: (data >> 16) as u16 as i16 as i32,
+            eval: (data >> 32) as u16 as i16 as i32,
+            depth: (data >> 48) as u8,
+            bound,
+            generation: (data >> 56) as u8 & GENERATION_MASK,
+        })
+    }
+}
+
+// the key is stored xored with the data so a torn write from another thread
+// fails verification instead of returning a mismatched entry
+#[derive(Default)]
+struct Slot {
+    key: AtomicU64,
+    data: AtomicU64,
+}
+
+impl Slot {
+    fn load(&self) -> (u64, u64) {
+        let data = self.data.load(Ordering::Relaxed);
+        let key = self.key.load(Ordering::Relaxed) ^ data;
+        (key, data)
+    }
+
+    fn store(&self, key: u64, data: u64) {
+        self.key.store(key ^ data, Ordering::Relaxed);
+        self.data.store(data, Ordering::Relaxed);
+    }
+}
+
+#[derive(Default)]
+#[repr(align(64))]
+struct Bucket([Slot; BUCKET_SIZE]);
+
+pub struct TranspositionTable {
+    buckets: Vec<Bucket>,
+    generation: AtomicU8,
+}
+
+impl TranspositionTable {
+    pub fn new(megabytes: usize) -> Self {
+        let len = (megabytes.max(1) << 20) / size_of::<Bucket>();
+        let mut buckets = Vec::with_capacity(len);
+        buckets.resize_with(len, Bucket::default);
+        Self {
+            buckets,
+            generation: AtomicU8::new(0),
+        }
+    }
+
+    pub fn resize(&mut self, megabytes: usize) {
+        *self = Self::new(megabytes);
+    }
+
+    pub fn clear(&mut self) {
+        self.buckets.fill_with(Bucket::default);
+        self.generation.store(0, Ordering::Relaxed);
+    }
+
+    pub fn new_search(&self) {
+        let generation = (self.get_generation() + 1) & GENERATION_MASK;
+        self.generation.store(generation, Ordering::Relaxed);
+    }
+
+    pub fn get_generation(&self) -> u8 {
+        self.generation.load(Ordering::Relaxed)
+    }
+
+    pub fn probe(&self, hash: u64, ply: u32) -> Option<TTEntry> {
+        self.get_bucket(hash).0.iter().find_map(|slot| {
+            let (key, data) = slot.load();
+            if key != hash {
+                return None;
+            }
+            let mut entry = TTEntry::unpack(data)?;
+            entry.score = score_from_tt(entry.score, ply);
+            Some(entry)
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn store(
+        &self,
+        hash: u64,
+        ply: u32,
+        move16: u16,
+        score: i32,
+        eval: i32,
+        depth: u32,
+        bound: Bound,
+    ) {
+        let generation = self.get_generation();
+        let mut entry = TTEntry {
+            move16,
+            score: score_to_tt(score, ply),
+            eval,
+            depth: depth.min(u8::MAX as u32) as u8,
+            bound,
+            generation,
+        };
+        let bucket = self.get_bucket(hash);
+        let mut replace = &bucket.0[0];
+        let mut replace_value = i32::MAX;
+        for slot in &bucket.0 {
+            let (key, data) = slot.load();
+            let Some(old) = TTEntry::unpack(data) else {
+                replace = slot;
+                break;
+            };
+            if key == hash {
+                // don't lose the best move when re-storing without one
+                if move16 == 0 {
+                    entry.move16 = old.move16;
+                }
+                if bound == Bound::Exact || entry.depth as u32 + 2 >= old.depth as u32 {
+                    slot.store(hash, entry.pack());
+                }
+                return;
+            }
+            // prefer overwriting shallow entries from old searches
+            let age = generation.wrapping_sub(old.generation) & GENERATION_MASK;
+            let value = old.depth as i32 - 8 * age as i32;
+            if value < replace_value {
+                replace = slot;
+                replace_value = value;
+            }
+        }
+        replace.store(hash, entry.pack());
+    }
+
+    pub fn probe_perft(&self, hash: u64, depth: u32) -> Option<u64> {
+        let key = perft_key(hash, depth);
+        self.get_bucket(key).0.iter().find_map(|slot| {
+            let (slot_key, data) = slot.load();
+            (slot_key == key && data <= MAX_PERFT_NODES).then_some(data)
+        })
+    }
+
+    pub fn store_perft(&self, hash: u64, depth: u32, nodes: u64) {
+        if nodes > MAX_PERFT_NODES {
+            return;
+        }
+        let key = perft_key(hash, depth);
+        let bucket = self.get_bucket(key);
+        let mut replace = &bucket.0[0];
+        let mut replace_value = u64::MAX;
+        for slot in &bucket.0 {
+            let (slot_key, data) = slot.load();
+            if slot_key == key || data == 0 {
+                replace = slot;
+                break;
+            }
+            // search entries go first, then the counts cheapest to redo
+            let value = match TTEntry::unpack(data) {
+                Some(_) => 0,
+                None => data,
+            };
+            if value < replace_value {
+                replace = slot;
+                replace_value = value;
+            }
+        }
+        replace.store(key, nodes);
+    }
+
+    // perft that looks up and stores the count below every position
+    pub fn perft(&self, game: &mut GameState, depth: u32) -> u128 {
+        if depth <= 1 {
+            return game.perft(depth);
+        }
+        let hash = game.get_hash();
+        if let Some(nodes) = self.probe_perft(hash, depth) {
+            return nodes as u128;
+        }
+        let mut nodes = 0;
+        for move_ in game.legal_moves() {
+            game.make_move(move_);
+            nodes += self.perft(game, depth - 1);
+            game.pop_move();
+        }
+        self.store_perft(hash, depth, nodes.min(u64::MAX as u128) as u64);
+        nodes
+    }
+
+    // permille of sampled slots written during the current search
+    pub fn hashfull(&self) -> u32 {
+        let generation = self.get_generation();
+        let sample = self.buckets.iter().take(1000 / BUCKET_SIZE);
+        let (used, total) = sample.fold((0, 0), |(used, total), bucket| {
+            let used_here = bucket
+                .0
+                .iter()
+                .filter_map(|slot| TTEntry::unpack(slot.load().1))
+                .filter(|entry| entry.generation == generation)
+                .count() as u32;
+            (used + used_here, total + BUCKET_SIZE as u32)
+        });
+        match total {
+            0 => 0,
+            _ => used * 1000 / total,
+        }
+    }
+
+    fn get_bucket(&self, hash: u64) -> &Bucket {
+        let idx = ((hash as u128 * self.buckets.len() as u128) >> 64) as usize;
+        &self.buckets[idx]
+    }
+}
+
+impl Default for TranspositionTable {
+    fn default() -> Self {
+        Self::new(DEFAULT_HASH_MB)
+    }
+}
+
+fn perft_key(hash: u64, depth: u32) -> u64 {
+    hash ^ PERFT_KEY.wrapping_mul(depth as u64)
+}
+
+// mate scores are stored relative to the node rather than the root so they
+// stay correct when the position is reached at a different ply
+fn score_to_tt(score: i32, ply: u32) -> i32 {
+    match is_mate_score(score) {
+        true => score + score.signum() * ply as i32,
+        false => score,
+    }
+}
+
+fn score_from_tt(score: i32, ply: u32) -> i32 {
+    match is_mate_score(score) {
+        true => score - score.signum() * ply as i32,
+        false => score,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pack_round_trip() {
+        let entry = TTEntry {
+            move16: 0x1234,
+            score: -MATE_SCORE + 5,
+            eval: 217,
+            depth: 17,
+            bound: Bound::Upper,
+            generation: 63,
+        };
+        assert_eq!(TTEntry::unpack(entry.pack()), Some(entry));
+        assert_eq!(TTEntry::unpack(0), None);
+    }
+
+    #[test]
+    fn test_store_and_probe() {
+        let tt = TranspositionTable::new(1);
+        assert_eq!(tt.probe(42, 0), None);
+        tt.store(42, 0, 7, 35, 20, 4, Bound::Lower);
+        let entry = tt.probe(42, 0).unwrap();
+        assert_eq!(entry.move16, 7);
+        assert_eq!(entry.score, 35);
+        assert_eq!(entry.eval, 20);
+        assert_eq!(entry.depth, 4);
+        assert_eq!(entry.bound, Bound::Lower);
+        assert_eq!(tt.probe(43, 0), None);
+
+        // a shallower store without a move keeps the old move
+        tt.store(42, 0, 0, 50, 20, 3, Bound::Exact);
+        let entry = tt.probe(42, 0).unwrap();
+        assert_eq!(entry.move16, 7);
+        assert_eq!(entry.score, 50);
+    }
+
+    #[test]
+    fn test_mate_scores_are_ply_relative() {
+        let tt = TranspositionTable::new(1);
+        // mate in 3 plies found 2 plies below the root
+        tt.store(1, 2, 0, MATE_SCORE - 5, 0, 3, Bound::Exact);
+        // reached again 4 plies below the root it is a mate in 3 plies from there
+        assert_eq!(tt.probe(1, 4).unwrap().score, MATE_SCORE - 7);
+        assert_eq!(tt.probe(1, 2).unwrap().score, MATE_SCORE - 5);
+    }
+
+    #[test]
+    fn test_hashed_perft() {
+        let tt = TranspositionTable::new(1);
+        for (fen, depth, nodes) in [
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                4,
+                197_281,
+            ),
+            (
+                "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+                3,
+                97_862,
+            ),
+            ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 4, 43_238),
+            (
+                "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+                3,
+                9_467,
+            ),
+        ] {
+            let mut game = GameState::try_from_fen(fen).unwrap();
+            assert_eq!(game.perft(depth), nodes);
+            assert_eq!(tt.perft(&mut game, depth), nodes, "{fen}");
+            // the second time round the root count comes straight from the table
+            assert_eq!(tt.probe_perft(game.get_hash(), depth), Some(nodes as u64));
+            assert_eq!(tt.perft(&mut game, depth), nodes);
+            assert_eq!(game.to_fen().as_ref(), fen);
+        }
+    }
+
+    #[test]
+    fn test_perft_and_search_entries_coexist() {
+        let tt = TranspositionTable::new(1);
+        tt.store(42, 0, 7, 35, 20, 4, Bound::Lower);
+        tt.store_perft(42, 4, 1000);
+        // neither kind of entry is read as the other
+        assert_eq!(tt.probe(42, 0).unwrap().move16, 7);
+        assert_eq!(tt.probe_perft(42, 4), Some(1000));
+        assert_eq!(tt.probe_perft(42, 3), None);
+        assert_eq!(tt.probe(perft_key(42, 4), 0), None);
+    }
+
+    #[test]
+    fn test_hashfull_and_aging() {
+        let mut tt = TranspositionTable::new(1);
+        assert_eq!(tt.hashfull(), 0);
+        for hash in 0..1000u64 {
+            let hash = hash.wrapping_mul(0x9e37_79b9_7f4a_7c15);
+            tt.store(hash, 0, 0, 0, 0, 1, Bound::Exact);
+        }
+        assert!(tt.hashfull() > 0);
+        tt.new_search();
+        assert_eq!(tt.hashfull(), 0);
+        tt.clear();
+        assert_eq!(tt.get_generation(), 0);
+        assert_eq!(tt.probe(0, 0), None);
+    }
+}
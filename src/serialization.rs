@@ -0,0 +1,595 @@
+use crate::{
+    board::{Board, Square},
+    gamestate::{CastleRights, GameState, Move, MoveRecord},
+    pieces::{Color, Piece},
+};
+use alloc::string::String;
+use core::{fmt, marker::PhantomData, str::FromStr};
+use serde::{
+    de::{self, DeserializeSeed, SeqAccess, Visitor},
+    ser::SerializeTuple,
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+const BOARD_BYTES: usize = 32;
+
+struct StrVisitor<T>(PhantomData<T>);
+
+impl<T> Visitor<'_> for StrVisitor<T>
+where
+    T: FromStr<Err = &'static str>,
+{
+    type Value = T;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a string")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        v.parse().map_err(E::custom)
+    }
+}
+
+fn deserialize_str<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr<Err = &'static str>,
+{
+    deserializer.deserialize_str(StrVisitor(PhantomData))
+}
+
+fn deserialize_code<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: TryFrom<u8, Error = &'static str>,
+{
+    let code = u8::deserialize(deserializer)?;
+    T::try_from(code).map_err(de::Error::custom)
+}
+
+fn next_element<'de, A, T>(seq: &mut A, index: usize) -> Result<T, A::Error>
+where
+    A: SeqAccess<'de>,
+    T: Deserialize<'de>,
+{
+    seq.next_element()?
+        .ok_or_else(|| de::Error::invalid_length(index, &"more elements"))
+}
+
+impl Serialize for Square {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_str(self)
+        } else {
+            serializer.serialize_u8(self.as_u8())
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Square {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            deserialize_str(deserializer)
+        } else {
+            deserialize_code(deserializer)
+        }
+    }
+}
+
+impl Serialize for Piece {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_str(self)
+        } else {
+            serializer.serialize_u8(u8::from(*self))
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Piece {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            deserialize_str(deserializer)
+        } else {
+            deserialize_code(deserializer)
+        }
+    }
+}
+
+impl Serialize for CastleRights {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(self.to_fen())
+        } else {
+            serializer.serialize_u8(u8::from(*self))
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for CastleRights {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            deserialize_str(deserializer)
+        } else {
+            deserialize_code(deserializer)
+        }
+    }
+}
+
+// binary boards are 64 nibbles, 0 for an empty square and piece code + 1 otherwise
+impl Serialize for Board {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            return serializer.serialize_str(&self.to_fen());
+        }
+        let mut bytes = [0u8; BOARD_BYTES];
+        for square in self.get_occupied().iter_forward() {
+            let code = self.get_square(square).map_or(0, |p| u8::from(p) + 1);
+            bytes[square.as_usize() / 2] |= code << (4 * (square.as_usize() % 2));
+        }
+        let mut tuple = serializer.serialize_tuple(BOARD_BYTES)?;
+        for byte in bytes {
+            tuple.serialize_element(&byte)?;
+        }
+        tuple.end()
+    }
+}
+
+struct BoardVisitor;
+
+impl<'de> Visitor<'de> for BoardVisitor {
+    type Value = Board;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("32 packed board bytes")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut board = Board::default();
+        for index in 0..BOARD_BYTES {
+            let byte: u8 = next_element(&mut seq, index)?;
+            for (offset, code) in [(0, byte & 0xf), (1, byte >> 4)] {
+                if code == 0 {
+                    continue;
+                }
+                let piece = Piece::try_from(code - 1).map_err(de::Error::custom)?;
+                board.set_square(Square::new((2 * index + offset) as u8), piece);
+            }
+        }
+        Ok(board)
+    }
+}
+
+impl<'de> Deserialize<'de> for Board {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            deserialize_str(deserializer)
+        } else {
+            deserializer.deserialize_tuple(BOARD_BYTES, BoardVisitor)
+        }
+    }
+}
+
+// positions keep only what a fen records, the move history is not serialized
+impl Serialize for GameState {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            return serializer.serialize_str(&self.to_fen());
+        }
+        let mut tuple = serializer.serialize_tuple(6)?;
+        tuple.serialize_element(self.board())?;
+        tuple.serialize_element(&(self.turn() as u8))?;
+        tuple.serialize_element(&self.castle_rights())?;
+        tuple.serialize_element(&self.ep_square())?;
+        tuple.serialize_element(&self.halfmove_clock())?;
+        tuple.serialize_element(&self.fullmove_number())?;
+        tuple.end()
+    }
+}
+
+struct GameStateVisitor;
+
+impl<'de> Visitor<'de> for GameStateVisitor {
+    type Value = GameState;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a packed position")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let board: Board = next_element(&mut seq, 0)?;
+        let turn = match next_element::<_, u8>(&mut seq, 1)? {
+            0 => Color::White,
+            1 => Color::Black,
+            _ => return Err(de::Error::custom("Invalid color")),
+        };
+        let castle = next_element(&mut seq, 2)?;
+        let ep_target = next_element(&mut seq, 3)?;
+        let half_moves = next_element(&mut seq, 4)?;
+        let full_moves = next_element(&mut seq, 5)?;
+        GameState::try_from_parts(board, turn, castle, ep_target, half_moves, full_moves)
+            .map_err(de::Error::custom)
+    }
+}
+
+impl<'de> Deserialize<'de> for GameState {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            deserialize_str(deserializer)
+        } else {
+            deserializer.deserialize_tuple(6, GameStateVisitor)
+        }
+    }
+}
+
+// human readable moves add the kind of move to the uci string so they can be
+// read back without the position they were played in. Binary moves are
+// (kind, from, to, extra) where extra holds the promotion piece, the captured
+// en passant pawn or the castling color.
+impl Serialize for Move {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            return ReadableMove {
+                uci: self.to_uci().into(),
+                kind: move_kind(*self).into(),
+            }
+            .serialize(serializer);
+        }
+        let (kind, extra) = match *self {
+            Move::MovePiece { .. } => (0u8, 0u8),
+            Move::MoveKing { .. } => (1, 0),
+            Move::PromotePawn { promotion, .. } => (2, u8::from(promotion)),
+            Move::MovePawnDouble { .. } => (3, 0),
+            Move::EnPassant { ep, .. } => (4, ep.as_u8()),
+            Move::KingSideCastle { color } => (5, color as u8),
+            Move::QueenSideCastle { color } => (6, color as u8),
+        };
+        let mut tuple = serializer.serialize_tuple(4)?;
+        tuple.serialize_element(&kind)?;
+        tuple.serialize_element(&self.get_from())?;
+        tuple.serialize_element(&self.get_to())?;
+        tuple.serialize_element(&extra)?;
+        tuple.end()
+    }
+}
+
+struct MoveVisitor;
+
+impl<'de> Visitor<'de> for MoveVisitor {
+    type Value = Move;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a packed move")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let kind: u8 = next_element(&mut seq, 0)?;
+        let from: Square = next_element(&mut seq, 1)?;
+        let to: Square = next_element(&mut seq, 2)?;
+        let extra: u8 = next_element(&mut seq, 3)?;
+        let color = || match extra {
+            0 => Ok(Color::White),
+            1 => Ok(Color::Black),
+            _ => Err(de::Error::custom("Invalid color")),
+        };
+        let move_ = match kind {
+            0 => Move::MovePiece { from, to },
+            1 => Move::MoveKing { from, to },
+            2 => Move::PromotePawn {
+                from,
+                to,
+                promotion: Piece::try_from(extra).map_err(de::Error::custom)?,
+            },
+            3 => Move::MovePawnDouble { from, to },
+            4 => Move::EnPassant {
+                from,
+                to,
+                ep: Square::try_from(extra).map_err(de::Error::custom)?,
+            },
+            5 => Move::KingSideCastle { color: color()? },
+            6 => Move::QueenSideCastle { color: color()? },
+            _ => return Err(de::Error::custom("Invalid move kind")),
+        };
+        Ok(move_)
+    }
+}
+
+impl<'de> Deserialize<'de> for Move {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            let move_ = ReadableMove::deserialize(deserializer)?;
+            move_from_kind(&move_.kind, &move_.uci).map_err(de::Error::custom)
+        } else {
+            deserializer.deserialize_tuple(4, MoveVisitor)
+        }
+    }
+}
+
+// reads a bare uci string, which does not say which kind of move it is, by
+// looking it up in a position
+pub struct MoveSeed<'a>(pub &'a GameState);
+
+impl<'de> DeserializeSeed<'de> for MoveSeed<'_> {
+    type Value = Move;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        if !deserializer.is_human_readable() {
+            return Move::deserialize(deserializer);
+        }
+        struct UciVisitor<'a>(&'a GameState);
+
+        impl Visitor<'_> for UciVisitor<'_> {
+            type Value = Move;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a uci move")
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                self.0.parse_move(v).map_err(E::custom)
+            }
+        }
+        deserializer.deserialize_str(UciVisitor(self.0))
+    }
+}
+
+// records store the ep target square like a fen does, not the square of the
+// pawn that can be taken
+fn ep_target(pawn: Square) -> Square {
+    match pawn.as_u8() {
+        code @ 24..=31 => Square::new(code - 8),
+        code => Square::new(code + 8),
+    }
+}
+
+fn ep_pawn(target: Square) -> Result<Square, &'static str> {
+    match target.as_u8() {
+        code @ 16..=23 => Ok(Square::new(code + 8)),
+        code @ 40..=47 => Ok(Square::new(code - 8)),
+        _ => Err("Invalid en passant square"),
+    }
+}
+
+const MOVE_KINDS: [&str; 7] = [
+    "piece",
+    "king",
+    "promotion",
+    "double",
+    "en_passant",
+    "kingside_castle",
+    "queenside_castle",
+];
+
+fn move_kind(move_: Move) -> &'static str {
+    let index = match move_ {
+        Move::MovePiece { .. } => 0,
+        Move::MoveKing { .. } => 1,
+        Move::PromotePawn { .. } => 2,
+        Move::MovePawnDouble { .. } => 3,
+        Move::EnPassant { .. } => 4,
+        Move::KingSideCastle { .. } => 5,
+        Move::QueenSideCastle { .. } => 6,
+    };
+    MOVE_KINDS[index]
+}
+
+// the kind says everything the uci string leaves to the position
+fn move_from_kind(kind: &str, uci: &str) -> Result<Move, &'static str> {
+    let square = |range| {
+        uci.get(range)
+            .ok_or("Invalid uci move")
+            .and_then(Square::try_from_alg)
+    };
+    let (from, to) = (square(0..2)?, square(2..4)?);
+    let color = match from.as_u8() < 32 {
+        true => Color::White,
+        false => Color::Black,
+    };
+    let move_ = match kind {
+        "piece" => Move::MovePiece { from, to },
+        "king" => Move::MoveKing { from, to },
+        "promotion" => {
+            let figure = uci.get(4..).and_then(|c| c.chars().next());
+            let figure = Piece::try_from(figure.ok_or("Invalid uci move")?)?.figure;
+            let color = match to.as_u8() >= 56 {
+                true => Color::White,
+                false => Color::Black,
+            };
+            Move::PromotePawn {
+                from,
+                to,
+                promotion: Piece { color, figure },
+            }
+        }
+        "double" => Move::MovePawnDouble { from, to },
+        "en_passant" => Move::EnPassant {
+            from,
+            to,
+            ep: Square::new(from.as_u8() & !7 | to.as_u8() & 7),
+        },
+        "kingside_castle" => Move::KingSideCastle { color },
+        "queenside_castle" => Move::QueenSideCastle { color },
+        _ => return Err("Invalid move kind"),
+    };
+    match move_.to_uci().as_ref() == uci {
+        true => Ok(move_),
+        false => Err("Invalid uci move"),
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "Move")]
+struct ReadableMove {
+    uci: String,
+    kind: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "MoveRecord")]
+struct SerialRecord {
+    move_: Move,
+    captured: Option<Piece>,
+    castle_rights: CastleRights,
+    ep: Option<Square>,
+    half_move: u16,
+    hash: u64,
+}
+
+impl Serialize for MoveRecord {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SerialRecord {
+            move_: self.move_,
+            captured: self.captured,
+            castle_rights: self.castle_rights,
+            ep: self.ep.map(ep_target),
+            half_move: self.half_move,
+            hash: self.hash,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for MoveRecord {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let record = SerialRecord::deserialize(deserializer)?;
+        let ep = match record.ep {
+            Some(target) => Some(ep_pawn(target).map_err(de::Error::custom)?),
+            None => None,
+        };
+        Ok(MoveRecord::new(
+            record.move_,
+            record.captured,
+            record.castle_rights,
+            ep,
+            record.half_move,
+            record.hash,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{board::squares::*, pieces::constants::*};
+
+    const FEN: &str = "r3k2r/1P6/8/3pP3/8/8/8/R3K2R w KQk d6 3 17";
+
+    #[test]
+    fn test_json_round_trip() {
+        let game = GameState::try_from_fen(FEN).unwrap();
+        let json = serde_json::to_string(&game).unwrap();
+        assert_eq!(json, format!("\"{}\"", FEN));
+        let decoded: GameState = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.to_fen(), game.to_fen());
+
+        assert_eq!(serde_json::to_string(&E4).unwrap(), "\"e4\"");
+        assert_eq!(serde_json::from_str::<Square>("\"e4\"").unwrap(), E4);
+        assert_eq!(serde_json::to_string(&BLACK_QUEEN).unwrap(), "\"q\"");
+        assert_eq!(serde_json::from_str::<Piece>("\"q\"").unwrap(), BLACK_QUEEN);
+        let castle = game.castle_rights();
+        assert_eq!(serde_json::to_string(&castle).unwrap(), "\"KQk\"");
+        assert_eq!(
+            serde_json::from_str::<CastleRights>("\"KQk\"").unwrap(),
+            castle
+        );
+    }
+
+    #[test]
+    fn test_json_moves() {
+        let mut game = GameState::try_from_fen(FEN).unwrap();
+        let move_ = game.parse_move("e5d6").unwrap();
+        let json = serde_json::to_string(&move_).unwrap();
+        assert_eq!(json, r#"{"uci":"e5d6","kind":"en_passant"}"#);
+        assert_eq!(serde_json::from_str::<Move>(&json).unwrap(), move_);
+        let mut deserializer = serde_json::Deserializer::from_str("\"e5d6\"");
+        let decoded = MoveSeed(&game).deserialize(&mut deserializer).unwrap();
+        assert_eq!(decoded, move_);
+        // moves need no position to be read back, even inside other types
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Played {
+            move_: Move,
+            ply: u32,
+        }
+        for (ply, move_) in game.legal_moves().into_iter().enumerate() {
+            let played = Played {
+                move_,
+                ply: ply as u32,
+            };
+            let json = serde_json::to_string(&played).unwrap();
+            assert_eq!(serde_json::from_str::<Played>(&json).unwrap(), played);
+        }
+        let json = r#"{"uci":"e2e4","kind":"kingside_castle"}"#;
+        assert!(serde_json::from_str::<Move>(json).is_err());
+
+        game.make_move(move_);
+        let json = serde_json::to_value(&game.history()[0]).unwrap();
+        assert_eq!(json["move_"]["uci"], "e5d6");
+        assert_eq!(json["move_"]["kind"], "en_passant");
+        assert_eq!(json["captured"], "p");
+        assert_eq!(json["castle_rights"], "KQk");
+        // the target square, as in the fen
+        assert_eq!(json["ep"], "d6");
+    }
+
+    #[test]
+    fn test_json_move_records() {
+        let mut game = GameState::try_from_fen(FEN).unwrap();
+        for uci in ["e5d6", "e8g8", "b7a8n", "g8g7", "e1c1", "g7g6", "a8b6"] {
+            game.make_move(game.parse_move(uci).unwrap());
+        }
+        let mut records = game.history().to_vec();
+        let mut game = GameState::default();
+        game.make_move(game.parse_move("e2e4").unwrap());
+        records.extend_from_slice(game.history());
+        for record in &records {
+            let json = serde_json::to_string(record).unwrap();
+            let decoded: MoveRecord = serde_json::from_str(&json).unwrap();
+            assert_eq!(decoded.move_, record.move_, "{json}");
+            assert_eq!(decoded.captured, record.captured);
+            assert_eq!(decoded.castle_rights, record.castle_rights);
+            assert_eq!(decoded.ep, record.ep);
+            assert_eq!(decoded.half_move, record.half_move);
+            assert_eq!(decoded.hash, record.hash);
+        }
+        let json = r#"{"move_":{"uci":"e2e4","kind":"castle"},"captured":null,"castle_rights":"-","ep":null,"half_move":0,"hash":0}"#;
+        assert!(serde_json::from_str::<MoveRecord>(json).is_err());
+        let json = r#"{"move_":{"uci":"e2e4","kind":"double"},"captured":null,"castle_rights":"-","ep":"e4","half_move":0,"hash":0}"#;
+        assert!(serde_json::from_str::<MoveRecord>(json).is_err());
+    }
+
+    #[test]
+    fn test_json_rejects_bad_fen() {
+        let json = "\"rnbqkbnrr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1\"";
+        assert!(serde_json::from_str::<GameState>(json).is_err());
+        assert!(serde_json::from_str::<Board>("\"9/8/8/8/8/8/8/8\"").is_err());
+        assert!(serde_json::from_str::<Board>("\"44p/8/8/8/8/8/8/8\"").is_err());
+    }
+
+    #[test]
+    fn test_binary_round_trip() {
+        let mut game = GameState::try_from_fen(FEN).unwrap();
+        let bytes = bincode::serialize(&game).unwrap();
+        assert!(bytes.len() < FEN.len());
+        let decoded: GameState = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(decoded.to_fen(), game.to_fen());
+
+        for move_ in game.legal_moves() {
+            let bytes = bincode::serialize(&move_).unwrap();
+            assert_eq!(bytes.len(), 4);
+            assert_eq!(bincode::deserialize::<Move>(&bytes).unwrap(), move_);
+        }
+        // a castle with a corrupt color, and an unknown kind
+        assert!(bincode::deserialize::<Move>(&[5, 4, 6, 1]).is_ok());
+        assert!(bincode::deserialize::<Move>(&[5, 4, 6, 7]).is_err());
+        assert!(bincode::deserialize::<Move>(&[7, 4, 6, 0]).is_err());
+
+        let move_ = game.parse_move("b7b8q").unwrap();
+        game.make_move(move_);
+        let record = &game.history()[0];
+        let bytes = bincode::serialize(record).unwrap();
+        let decoded: crate::MoveRecord = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(decoded.move_, record.move_);
+        assert_eq!(decoded.castle_rights, record.castle_rights);
+        assert_eq!(decoded.half_move, 3);
+    }
+}
@@ -0,0 +1,65 @@
+use crate::{
+    board::Square,
+    gamestate::CastleRights,
+    pieces::{Color, Piece},
+};
+
+const SEED: u64 = 0x3243_f6a8_885a_308d;
+
+static PIECE_KEYS: [[u64; 64]; 12] = gen_piece_keys();
+
+static CASTLE_KEYS: [u64; 16] = gen_keys(SEED ^ 0xc0ffee);
+
+static EP_KEYS: [u64; 8] = gen_keys(SEED ^ 0xbeef);
+
+const SIDE_KEY: u64 = splitmix64(SEED ^ 0xf00d).1;
+
+pub fn piece_key(piece: Piece, square: Square) -> u64 {
+    PIECE_KEYS[u8::from(piece) as usize][square.as_usize()]
+}
+
+pub fn castle_key(castle: CastleRights) -> u64 {
+    CASTLE_KEYS[u8::from(castle) as usize]
+}
+
+pub fn ep_key(square: Square) -> u64 {
+    EP_KEYS[square.get_col().as_u8() as usize]
+}
+
+pub fn side_key(color: Color) -> u64 {
+    match color {
+        Color::White => 0,
+        Color::Black => SIDE_KEY,
+    }
+}
+
+const fn splitmix64(state: u64) -> (u64, u64) {
+    let state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    (state, z ^ (z >> 31))
+}
+
+const fn gen_keys<const N: usize>(seed: u64) -> [u64; N] {
+    let mut array = [0; N];
+    let mut state = seed;
+    let mut counter = 0;
+    while counter < N {
+        let (next_state, key) = splitmix64(state);
+        array[counter] = key;
+        state = next_state;
+        counter += 1;
+    }
+    array
+}
+
+const fn gen_piece_keys() -> [[u64; 64]; 12] {
+    let mut array = [[0; 64]; 12];
+    let mut counter = 0;
+    while counter < 12 {
+        array[counter] = gen_keys(SEED ^ counter as u64);
+        counter += 1;
+    }
+    array
+}